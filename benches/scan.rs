@@ -0,0 +1,36 @@
+//! count_byteと、1バイトずつ数える愚直な実装の速さを比べる
+//! 
+//! `cargo bench --bench scan`で実行します。(`--features memchr`でmemchr版になります)
+
+use std::{hint::black_box, time::{Duration, Instant}};
+
+use my_buf_bytes::BufBytes;
+
+// 1行80バイトの、16MiBのログもどき
+fn log_data() -> Vec<u8> {
+    let line: Vec<u8> = (0..79).map(|i| b'a' + (i % 26) as u8).chain([b'\n']).collect();
+    line.iter().copied().cycle().take(16 << 20).collect()
+}
+
+fn measure(name: &str, mut f: impl FnMut() -> u64) -> Duration {
+    let mut best = Duration::MAX;
+    let mut count = 0;
+    for _ in 0..5 {
+        let start = Instant::now();
+        count = black_box(f());
+        best = best.min(start.elapsed());
+    }
+    println!("{:<12} {:>10.2?} ({} lines)", name, best, count);
+    best
+}
+
+fn main() {
+    let data = log_data();
+    let naive = measure("naive", || {
+        BufBytes::new(&data[..]).unwrap().filter(|&b| b == b'\n').count() as u64
+    });
+    let fast = measure("count_byte", || {
+        BufBytes::new(&data[..]).unwrap().count_byte(b'\n').unwrap()
+    });
+    println!("speedup: {:.1}x", naive.as_secs_f64() / fast.as_secs_f64());
+}
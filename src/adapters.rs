@@ -0,0 +1,63 @@
+//! # adapters
+//! [`BufBytes`](crate::BufBytes)を包んで、返すバイトに手を加えるイテレーターたちです。  
+//! それぞれ`BufBytes`のメソッドから作成します。  
+
+mod as_u16;
+mod ascii_case;
+mod ascii_strings;
+mod bits;
+mod chars_utf32;
+mod cobs;
+mod csv_fields;
+mod delta;
+mod downsample;
+mod enumerate_lines;
+mod gray_code;
+mod grep;
+mod hex;
+mod line_range;
+mod lines;
+mod map_bytes;
+mod paragraphs;
+mod records;
+mod reverse_bits;
+mod run_length;
+mod sample;
+mod segments;
+mod std_bytes;
+mod step_by;
+mod swap_endian;
+mod take_until;
+mod throttle;
+mod wrap;
+mod xor;
+
+pub use as_u16::AsU16;
+pub use ascii_case::{AsciiLower, AsciiUpper};
+pub use ascii_strings::AsciiStrings;
+pub use bits::{Bits, NrziDecode, NrziEncode, Repack7};
+pub use chars_utf32::CharsUtf32;
+pub use cobs::CobsDecode;
+pub use csv_fields::CsvFields;
+pub use delta::{DeltaDecode, DeltaEncode};
+pub use downsample::Downsample;
+pub use enumerate_lines::EnumerateLines;
+pub use gray_code::{FromGrayCode, GrayCode};
+pub use grep::Grep;
+pub use hex::{HexDecode, HexEncode};
+pub use line_range::LineRange;
+pub use lines::{Lines, TrimmedLines};
+pub use map_bytes::MapBytes;
+pub use paragraphs::Paragraphs;
+pub use records::Records;
+pub use reverse_bits::ReverseBits;
+pub use run_length::RunLength;
+pub use sample::Sample;
+pub use segments::Segments;
+pub use std_bytes::{StdBytesCompat, StdBytesReader};
+pub use step_by::StepBy;
+pub use swap_endian::SwapEndian;
+pub use take_until::TakeUntil;
+pub use throttle::Throttle;
+pub use wrap::Wrap;
+pub use xor::XorDecrypt;
@@ -0,0 +1,99 @@
+use std::io::{Read, Result};
+
+use crate::BufBytes;
+
+/// 2バイトずつ`u16`にまとめて返すイテレーター
+/// 
+/// [`BufBytes::as_u16_le`]で作成します。
+#[derive(Debug)]
+pub struct AsU16<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    ignore_odd_tail: bool,
+    done: bool,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// リトルエンディアンの`u16`として、2バイトずつ読むイテレーターにする
+    /// 
+    /// 16bit PCMのサンプルなどを読むのに使えます。  
+    /// 最後に1バイト余ったときは`UnexpectedEof`を、  
+    /// io処理でエラーが起きたときはそのエラーを、一度返して終わります。  
+    /// 余った1バイトを黙って捨てたいときは[`AsU16::ignore_odd_tail`]を使ってください。
+    pub fn as_u16_le(self) -> AsU16<B> {
+        AsU16 { inner: self, ignore_odd_tail: false, done: false }
+    }
+}
+
+impl<B> AsU16<B>
+where
+    B: Read,
+{
+    /// 最後に余った1バイトをエラーにせず、捨てる
+    pub fn ignore_odd_tail(mut self) -> Self {
+        self.ignore_odd_tail = true;
+        self
+    }
+}
+
+impl<B> Iterator for AsU16<B>
+where
+    B: Read,
+{
+    type Item = Result<u16>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut pair = [0; 2];
+        let n = self.inner.fill_slice(&mut pair);
+        if n == 2 {
+            return Some(Ok(u16::from_le_bytes(pair)));
+        }
+        self.done = true;
+        // ちょうど区切りでEOFになったか、端数を捨てるならそこで終わり
+        let eof = self.inner.error.is_none();
+        if eof && (n == 0 || self.ignore_odd_tail) {
+            return None;
+        }
+        Some(Err(self.inner.short_read_error()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::BufBytes;
+
+    // 3byteバッファの境界をまたいでもペアが組める
+    #[test]
+    fn as_u16_le_test() {
+        let samples: [u16; 4] = [0x0102, 0xfffe, 0, 0x8000];
+        let base: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let res: Vec<u16> = BufBytes::with_capacity(&base[..], 3).unwrap()
+            .as_u16_le()
+            .map(|s| s.unwrap())
+            .collect();
+        assert_eq!(res, samples);
+    }
+
+    // 奇数長の端数は、UnexpectedEofにするか捨てるかを選べる
+    #[test]
+    fn as_u16_le_odd_tail_test() {
+        let base = [0x34, 0x12, 0xff];
+        let mut iter = BufBytes::new(&base[..]).unwrap().as_u16_le();
+        assert_eq!(iter.next().unwrap().unwrap(), 0x1234);
+        assert_eq!(iter.next().unwrap().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        assert!(iter.next().is_none());
+
+        let iter = BufBytes::new(&base[..]).unwrap().as_u16_le().ignore_odd_tail();
+        assert_eq!(iter.map(|s| s.unwrap()).collect::<Vec<u16>>(), [0x1234]);
+    }
+}
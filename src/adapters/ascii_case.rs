@@ -0,0 +1,90 @@
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// バイトをascii小文字にして返すイテレーター
+/// 
+/// [`BufBytes::to_ascii_lowercase_iter`]で作成します。
+#[derive(Debug)]
+pub struct AsciiLower<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+}
+
+/// バイトをascii大文字にして返すイテレーター
+/// 
+/// [`BufBytes::to_ascii_uppercase_iter`]で作成します。
+#[derive(Debug)]
+pub struct AsciiUpper<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 各バイトをascii小文字に変換するイテレーターにする
+    /// 
+    /// 非asciiのバイトはそのまま返します。
+    pub fn to_ascii_lowercase_iter(self) -> AsciiLower<B> {
+        AsciiLower { inner: self }
+    }
+
+    /// 各バイトをascii大文字に変換するイテレーターにする
+    /// 
+    /// 非asciiのバイトはそのまま返します。
+    pub fn to_ascii_uppercase_iter(self) -> AsciiUpper<B> {
+        AsciiUpper { inner: self }
+    }
+}
+
+impl<B> Iterator for AsciiLower<B>
+where
+    B: Read,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|b| b.to_ascii_lowercase())
+    }
+}
+
+impl<B> Iterator for AsciiUpper<B>
+where
+    B: Read,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|b| b.to_ascii_uppercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    // 大文字小文字混在の入力が全部小文字になり、非asciiはそのまま
+    #[test]
+    fn ascii_lowercase_test() {
+        let base = "HeLLo, WoRLD! あ".as_bytes();
+        let bytes = BufBytes::with_capacity(base, 4).unwrap();
+
+        let res: Vec<u8> = bytes.to_ascii_lowercase_iter().collect();
+        assert_eq!(res, "hello, world! あ".as_bytes());
+    }
+
+    #[test]
+    fn ascii_uppercase_test() {
+        let base = "HeLLo, WoRLD! あ".as_bytes();
+        let bytes = BufBytes::with_capacity(base, 4).unwrap();
+
+        let res: Vec<u8> = bytes.to_ascii_uppercase_iter().collect();
+        assert_eq!(res, "HELLO, WORLD! あ".as_bytes());
+    }
+}
@@ -0,0 +1,64 @@
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// 印字可能なASCII文字列を取り出すイテレーター
+/// 
+/// [`BufBytes::ascii_strings`]で作成します。
+#[derive(Debug)]
+pub struct AsciiStrings<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    min_len: usize,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 印字可能なASCII(0x20〜0x7E)がmin_len文字以上続く部分を、`String`で返すイテレーターにする
+    /// 
+    /// Unixの`strings`コマンドのように、バイナリに埋め込まれた文字列を探すのに使えます。  
+    /// 印字できないバイトで区切り、min_len文字に満たない並びは捨てます。
+    pub fn ascii_strings(self, min_len: usize) -> AsciiStrings<B> {
+        AsciiStrings { inner: self, min_len }
+    }
+}
+
+impl<B> Iterator for AsciiStrings<B>
+where
+    B: Read,
+{
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut s = String::new();
+        loop {
+            match self.inner.next() {
+                Some(b @ 0x20..=0x7e) => s.push(b as char),
+                byte => {
+                    if !s.is_empty() && s.len() >= self.min_len {
+                        return Some(s);
+                    }
+                    byte?;
+                    s.clear();
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    // バイナリの中から、4文字以上の文字列だけを取り出す
+    #[test]
+    fn ascii_strings_test() {
+        let base = b"\x00\x01ELF\x7fhello world\x00\xffab\x00\x02libc.so.6\n\x90tail";
+        let strings = BufBytes::with_capacity(&base[..], 4).unwrap().ascii_strings(4);
+        assert_eq!(strings.collect::<Vec<String>>(), ["hello world", "libc.so.6", "tail"]);
+    }
+}
@@ -0,0 +1,201 @@
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// 1ビットずつ返すイテレーター
+/// 
+/// [`BufBytes::bits`]で作成します。
+#[derive(Debug)]
+pub struct Bits<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    byte: u8,
+    // byteのうち、まだ返していないビット数
+    left: u32,
+}
+
+/// ビット列を7ビットずつに詰め直して返すイテレーター
+/// 
+/// [`BufBytes::repack_7bit`]で作成します。
+#[derive(Debug)]
+pub struct Repack7<B>
+where
+    B: Read,
+{
+    bits: Bits<B>,
+}
+
+/// ビット列をNRZIの信号にして返すイテレーター
+/// 
+/// [`BufBytes::nrzi_encode`]で作成します。
+#[derive(Debug)]
+pub struct NrziEncode<B>
+where
+    B: Read,
+{
+    bits: Bits<B>,
+    level: bool,
+}
+
+/// NRZIの信号をビット列に戻して返すイテレーター
+/// 
+/// [`BufBytes::nrzi_decode`]で作成します。
+#[derive(Debug)]
+pub struct NrziDecode<B>
+where
+    B: Read,
+{
+    bits: Bits<B>,
+    level: bool,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 各バイトを上位ビット(MSB)から順に、1ビットずつ`bool`で返すイテレーターにする
+    /// 
+    /// LSBから読みたいときは、[`reverse_bits_iter`](Self::reverse_bits_iter)と組み合わせてください。
+    pub fn bits(self) -> Bits<B> {
+        Bits { inner: self, byte: 0, left: 0 }
+    }
+
+    /// 入力を1本のビット列とみなして、7ビットずつを1バイト(0x00〜0x7F)にして返すイテレーターにする
+    /// 
+    /// MIDIのSysExなど、最上位ビットを使えない形式に詰め直すのに使います。  
+    /// ビットは[`bits`](Self::bits)と同じくMSBから順に取り出します。  
+    /// 最後に7ビットに満たない端数が残ったときは、下位を0で埋めた1バイトにします。
+    pub fn repack_7bit(self) -> Repack7<B> {
+        Repack7 { bits: self.bits() }
+    }
+
+    /// [`bits`](Self::bits)のビット列を、NRZIで符号化した信号のレベルにするイテレーターにする
+    /// 
+    /// ビットが1なら信号を反転し、0ならそのまま維持します。 信号は`false`(Low)から始めます。
+    pub fn nrzi_encode(self) -> NrziEncode<B> {
+        NrziEncode { bits: self.bits(), level: false }
+    }
+
+    /// [`bits`](Self::bits)を信号のレベルとみなして、NRZIを復号したビット列にするイテレーターにする
+    /// 
+    /// 前のレベルから変わっていれば1、変わっていなければ0です。  
+    /// [`nrzi_encode`](Self::nrzi_encode)の逆変換で、信号は`false`(Low)から始まったものとします。
+    pub fn nrzi_decode(self) -> NrziDecode<B> {
+        NrziDecode { bits: self.bits(), level: false }
+    }
+}
+
+impl<B> Iterator for Bits<B>
+where
+    B: Read,
+{
+    type Item = bool;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.left == 0 {
+            self.byte = self.inner.next()?;
+            self.left = 8;
+        }
+        self.left -= 1;
+        Some(self.byte >> self.left & 1 == 1)
+    }
+}
+
+impl<B> Iterator for Repack7<B>
+where
+    B: Read,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut out = 0;
+        let mut taken = 0;
+        for bit in self.bits.by_ref().take(7) {
+            out = out << 1 | bit as u8;
+            taken += 1;
+        }
+        if taken == 0 {
+            return None;
+        }
+        // 端数は下位を0で埋める
+        Some(out << (7 - taken))
+    }
+}
+
+impl<B> Iterator for NrziEncode<B>
+where
+    B: Read,
+{
+    type Item = bool;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.level ^= self.bits.next()?;
+        Some(self.level)
+    }
+}
+
+impl<B> Iterator for NrziDecode<B>
+where
+    B: Read,
+{
+    type Item = bool;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let level = self.bits.next()?;
+        let bit = level != self.level;
+        self.level = level;
+        Some(bit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    #[test]
+    fn bits_test() {
+        let res: Vec<bool> = BufBytes::new(&[0b1010_0001, 0b0100_0000][..]).unwrap().bits().collect();
+        let expected = [
+            true, false, true, false, false, false, false, true,
+            false, true, false, false, false, false, false, false,
+        ];
+        assert_eq!(res, expected);
+    }
+
+    // 7byte(56bit)はちょうど8つになり、端数は0で埋める
+    #[test]
+    fn repack_7bit_test() {
+        let base = [0xff; 7];
+        let res: Vec<u8> = BufBytes::with_capacity(&base[..], 3).unwrap().repack_7bit().collect();
+        assert_eq!(res, [0x7f; 8]);
+
+        // 1000_0001 0111_1111 -> 1000000 1011111 11(00000)
+        let base = [0b1000_0001, 0b0111_1111];
+        let res: Vec<u8> = BufBytes::new(&base[..]).unwrap().repack_7bit().collect();
+        assert_eq!(res, [0b100_0000, 0b101_1111, 0b110_0000]);
+    }
+
+    // MSBから8ビットずつ詰めてバイトに戻す
+    fn pack(bits: &[bool]) -> Vec<u8> {
+        bits.chunks(8).map(|c| c.iter().fold(0, |acc, &b| acc << 1 | b as u8)).collect()
+    }
+
+    #[test]
+    fn nrzi_encode_test() {
+        // 1で反転、0で維持
+        let res: Vec<bool> = BufBytes::new(&[0b1011_0001][..]).unwrap().nrzi_encode().collect();
+        assert_eq!(res, [true, true, false, true, true, true, true, false]);
+        assert_eq!(pack(&res), [0b1101_1110]);
+    }
+
+    // 符号化して復号すると元のビット列に戻る
+    #[test]
+    fn nrzi_round_trip_test() {
+        let base: Vec<u8> = (0..=255).collect();
+        let signal: Vec<bool> = BufBytes::with_capacity(&base[..], 7).unwrap().nrzi_encode().collect();
+        let res: Vec<bool> = BufBytes::with_capacity(&pack(&signal)[..], 7).unwrap().nrzi_decode().collect();
+        assert_eq!(pack(&res), base);
+    }
+}
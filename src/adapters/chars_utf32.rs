@@ -0,0 +1,109 @@
+use std::io::{Error, ErrorKind, Read, Result};
+
+use crate::BufBytes;
+
+/// UTF-32として4バイトずつ文字を返すイテレーター
+/// 
+/// [`BufBytes::chars_utf32_le`]で作成します。
+#[derive(Debug)]
+pub struct CharsUtf32<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    done: bool,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// UTF-32LEとして、1文字(4バイト)ずつ読むイテレーターに変換する
+    /// 
+    /// サロゲートや0x10FFFFを超える値は`InvalidData`になります。  
+    /// 最後に4バイトに満たない端数が残ったときは`UnexpectedEof`を、  
+    /// io処理でエラーが起きたときはそのエラーを、一度返して終わります。
+    pub fn chars_utf32_le(self) -> CharsUtf32<B> {
+        CharsUtf32 { inner: self, done: false }
+    }
+}
+
+impl<B> Iterator for CharsUtf32<B>
+where
+    B: Read,
+{
+    type Item = Result<char>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        // ちょうど文字の区切りで終わっていれば、EOF
+        let res = match self.inner.peek_n(1) {
+            Ok([]) => return None,
+            Ok(_) => self.inner.read_array::<4>(),
+            Err(e) => Err(e),
+        };
+        // 端数やio処理のエラーを返したら、それで終わり
+        let code = match res {
+            Ok(bytes) => u32::from_le_bytes(bytes),
+            Err(e) => {
+                self.done = true;
+                return Some(Err(e));
+            },
+        };
+        Some(char::from_u32(code).ok_or_else(|| {
+            Error::new(ErrorKind::InvalidData, format!("invalid utf-32 code point: {:#x}", code))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::BufBytes;
+
+    fn encode(s: &str) -> Vec<u8> {
+        s.chars().flat_map(|c| (c as u32).to_le_bytes()).collect()
+    }
+
+    // 4バイトの区切りがバッファをまたいでも読める
+    #[test]
+    fn chars_utf32_le_test() {
+        let base = encode("aあ🦀z");
+        // 6byteバッファなので、2文字目以降はバッファをまたぐ
+        let bytes = BufBytes::with_capacity(&base[..], 6).unwrap();
+
+        let res: String = bytes.chars_utf32_le().map(|c| c.unwrap()).collect();
+        assert_eq!(res, "aあ🦀z");
+    }
+
+    // サロゲートと範囲外はInvalidDataで、その後も読み進められる
+    #[test]
+    fn chars_utf32_le_invalid_test() {
+        let mut base = Vec::new();
+        base.extend(0xD800u32.to_le_bytes());
+        base.extend(0x110000u32.to_le_bytes());
+        base.extend(encode("a"));
+        let bytes = BufBytes::with_capacity(&base[..], 8).unwrap();
+
+        let res: Vec<_> = bytes.chars_utf32_le().collect();
+        assert_eq!(res.len(), 3);
+        assert_eq!(res[0].as_ref().unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(res[1].as_ref().unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(*res[2].as_ref().unwrap(), 'a');
+    }
+
+    // 端数はUnexpectedEof
+    #[test]
+    fn chars_utf32_le_truncated_test() {
+        let mut base = encode("a");
+        base.extend([0x42, 0x00]);
+        let bytes = BufBytes::with_capacity(&base[..], 8).unwrap();
+
+        let res: Vec<_> = bytes.chars_utf32_le().collect();
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[1].as_ref().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+}
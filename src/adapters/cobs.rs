@@ -0,0 +1,148 @@
+use std::io::{Error, ErrorKind, Read, Result};
+
+use crate::BufBytes;
+
+/// `0x00`で区切られたCOBSのフレームを復号して返すイテレーター
+/// 
+/// [`BufBytes::cobs_decode`]で作成します。
+#[derive(Debug)]
+pub struct CobsDecode<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    // フレームの途中か
+    in_frame: bool,
+    // 今のブロックのコードバイトと、まだ返していないデータのバイト数
+    code: u8,
+    left: u8,
+    done: bool,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// `0x00`で区切られたCOBS(Consistent Overhead Byte Stuffing)のフレームを、元のバイトに戻すイテレーターにする
+    /// 
+    /// シリアル通信のフレームを読むのに使えます。 続くフレームは、復号したバイトをそのままつなげて返します。  
+    /// フレームの間に余分な`0x00`があっても、空のフレームとして読み飛ばします。  
+    /// ブロックの途中に`0x00`があるときは`InvalidData`を、  
+    /// フレームが`0x00`で終わらないままEOFになったときは`UnexpectedEof`を、  
+    /// io処理でエラーが起きたときはそのエラーを、一度返して終わります。
+    pub fn cobs_decode(self) -> CobsDecode<B> {
+        CobsDecode { inner: self, in_frame: false, code: 0, left: 0, done: false }
+    }
+}
+
+impl<B> CobsDecode<B>
+where
+    B: Read,
+{
+    /// エラーを返して終わる
+    fn fail(&mut self, e: Error) -> Option<Result<u8>> {
+        self.done = true;
+        Some(Err(e))
+    }
+}
+
+impl<B> Iterator for CobsDecode<B>
+where
+    B: Read,
+{
+    type Item = Result<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        loop {
+            let Some(byte) = self.inner.next() else {
+                // フレームの切れ目でEOFになったなら、きれいに終わり
+                if !self.in_frame && self.inner.error.is_none() {
+                    self.done = true;
+                    return None;
+                }
+                let e = self.inner.short_read_error();
+                return self.fail(e);
+            };
+            if self.left > 0 {
+                if byte == 0 {
+                    return self.fail(Error::new(ErrorKind::InvalidData, "zero byte inside a COBS block"));
+                }
+                self.left -= 1;
+                return Some(Ok(byte));
+            }
+            if !self.in_frame {
+                // フレームの始まりはコードバイト
+                if byte != 0 {
+                    self.in_frame = true;
+                    self.code = byte;
+                    self.left = byte - 1;
+                }
+                continue;
+            }
+            // ブロックの終わり。 0x00ならフレームも終わり
+            if byte == 0 {
+                self.in_frame = false;
+                continue;
+            }
+            // 0xFFのブロックのあとには、0x00を補わない
+            let zero = self.code != 0xff;
+            self.code = byte;
+            self.left = byte - 1;
+            if zero {
+                return Some(Ok(0));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::BufBytes;
+
+    fn decode(frames: &[u8]) -> Vec<Result<u8, ErrorKind>> {
+        BufBytes::with_capacity(frames, 3).unwrap().cobs_decode().map(|r| r.map_err(|e| e.kind())).collect()
+    }
+
+    // 既知の符号化例が元に戻る
+    #[test]
+    fn cobs_decode_test() {
+        let cases: [(&[u8], &[u8]); 5] = [
+            (&[0x01, 0x01, 0x00], &[0x00]),
+            (&[0x01, 0x01, 0x01, 0x00], &[0x00, 0x00]),
+            (&[0x03, 0x11, 0x22, 0x02, 0x33, 0x00], &[0x11, 0x22, 0x00, 0x33]),
+            (&[0x05, 0x11, 0x22, 0x33, 0x44, 0x00], &[0x11, 0x22, 0x33, 0x44]),
+            (&[0x02, 0x11, 0x01, 0x01, 0x01, 0x00], &[0x11, 0x00, 0x00, 0x00]),
+        ];
+        for (encoded, expected) in cases {
+            let res: Vec<u8> = decode(encoded).into_iter().map(Result::unwrap).collect();
+            assert_eq!(res, expected);
+        }
+
+        // 254バイトのブロックのあとには、0x00を補わない
+        let data: Vec<u8> = (1..=255).collect();
+        let mut encoded = vec![0xff];
+        encoded.extend(1..=254);
+        encoded.extend([0x02, 0xff, 0x00]);
+        let res: Vec<u8> = decode(&encoded).into_iter().map(Result::unwrap).collect();
+        assert_eq!(res, data);
+    }
+
+    // 続くフレームは、間の余分な0x00を飛ばしてつなげる
+    #[test]
+    fn cobs_decode_frames_test() {
+        let frames = [0x02, 0x11, 0x00, 0x00, 0x03, 0x22, 0x33, 0x00];
+        assert_eq!(decode(&frames), [Ok(0x11), Ok(0x22), Ok(0x33)]);
+    }
+
+    // 壊れたフレームはエラーになる
+    #[test]
+    fn cobs_decode_invalid_test() {
+        assert_eq!(decode(&[0x03, 0x11, 0x00, 0x22]), [Ok(0x11), Err(ErrorKind::InvalidData)]);
+        assert_eq!(decode(&[0x03, 0x11, 0x22]), [Ok(0x11), Ok(0x22), Err(ErrorKind::UnexpectedEof)]);
+    }
+}
@@ -0,0 +1,167 @@
+use std::io::{Error, ErrorKind, Read, Result};
+
+use crate::BufBytes;
+
+/// CSVのセルを1つずつ返すイテレーター
+/// 
+/// [`BufBytes::csv_fields`]で作成します。
+#[derive(Debug)]
+pub struct CsvFields<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    delim: u8,
+    // 直前のセルがデリミタで終わった(まだ同じレコードにセルが続く)
+    after_delim: bool,
+    record_end: bool,
+    done: bool,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// CSVのセルを1つずつ`Vec<u8>`で返すイテレーターにする
+    /// 
+    /// RFC 4180の基本的なケースに対応しています。
+    /// - `"`で囲まれたセルでは、デリミタや改行もセルの中身になる
+    /// - `"`で囲まれたセルの中の`""`は、1つの`"`にする
+    /// - 改行(`\n`か`\r\n`)でレコードが終わる。 [`CsvFields::record_ended`]で判定できる
+    /// 
+    /// 閉じられていない`"`は`InvalidData`になります。
+    pub fn csv_fields(self, delim: u8) -> CsvFields<B> {
+        CsvFields { inner: self, delim, after_delim: false, record_end: true, done: false }
+    }
+}
+
+impl<B> CsvFields<B>
+where
+    B: Read,
+{
+    /// 直前に返したセルが、レコードの最後のセルだったか
+    pub fn record_ended(&self) -> bool {
+        self.record_end
+    }
+
+    /// セルの区切り(デリミタ/改行/EOF)を見つけたときの処理
+    /// 
+    /// 区切りでなければfalseを返す
+    fn end_field(&mut self, byte: Option<u8>, field: &mut Vec<u8>) -> bool {
+        match byte {
+            Some(b) if b == self.delim => {
+                self.after_delim = true;
+                self.record_end = false;
+            },
+            Some(b'\n') => {
+                if field.ends_with(b"\r") {
+                    field.pop();
+                }
+                self.after_delim = false;
+                self.record_end = true;
+            },
+            None => {
+                self.after_delim = false;
+                self.record_end = true;
+            },
+            Some(_) => return false,
+        }
+        true
+    }
+
+    /// `"`で囲まれた部分を読む
+    fn read_quoted(&mut self, field: &mut Vec<u8>) -> Result<()> {
+        loop {
+            match self.inner.next() {
+                Some(b'"') => {
+                    if self.inner.peek() != Some(b'"') {
+                        return Ok(());
+                    }
+                    self.inner.next();
+                    field.push(b'"');
+                },
+                Some(b) => field.push(b),
+                None => return Err(Error::new(ErrorKind::InvalidData, "unterminated quoted field")),
+            }
+        }
+    }
+}
+
+impl<B> Iterator for CsvFields<B>
+where
+    B: Read,
+{
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let first = self.inner.next();
+        if first.is_none() && !self.after_delim {
+            // レコードの区切りでEOF
+            self.done = true;
+            return self.inner.error.is_some().then(|| Err(self.inner.short_read_error()));
+        }
+        let mut field = Vec::new();
+        let mut byte = first;
+        if byte == Some(b'"') {
+            if let Err(e) = self.read_quoted(&mut field) {
+                self.done = true;
+                return Some(Err(e));
+            }
+            byte = self.inner.next();
+        }
+        // 区切りが来るまでをセルにする
+        while !self.end_field(byte, &mut field) {
+            field.extend(byte);
+            byte = self.inner.next();
+        }
+        Some(Ok(field))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    fn parse(base: &[u8]) -> Vec<(Vec<u8>, bool)> {
+        let mut fields = BufBytes::with_capacity(base, 4).unwrap().csv_fields(b',');
+        let mut res = Vec::new();
+        while let Some(field) = fields.next() {
+            res.push((field.unwrap(), fields.record_ended()));
+        }
+        res
+    }
+
+    fn field(s: &str, record_end: bool) -> (Vec<u8>, bool) {
+        (s.as_bytes().to_vec(), record_end)
+    }
+
+    #[test]
+    fn csv_fields_test() {
+        let res = parse(b"a,b,c\r\n1,,3\n");
+        assert_eq!(res, vec![
+            field("a", false), field("b", false), field("c", true),
+            field("1", false), field("", false), field("3", true),
+        ]);
+    }
+
+    // クォート内のデリミタや改行は1フィールドになり、""は"になる
+    #[test]
+    fn csv_fields_quoted_test() {
+        let res = parse(b"\"x,y\",\"line1\nline2\",\"say \"\"hi\"\"\"\nlast,");
+        assert_eq!(res, vec![
+            field("x,y", false), field("line1\nline2", false), field("say \"hi\"", true),
+            field("last", false), field("", true),
+        ]);
+    }
+
+    #[test]
+    fn csv_fields_unterminated_test() {
+        let mut fields = BufBytes::with_capacity(&b"a,\"open"[..], 4).unwrap().csv_fields(b',');
+        assert_eq!(fields.next().unwrap().unwrap(), b"a");
+        assert_eq!(fields.next().unwrap().unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+        assert!(fields.next().is_none());
+    }
+}
@@ -0,0 +1,95 @@
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// 前のバイトとの差分を返すイテレーター
+/// 
+/// [`BufBytes::delta_encode`]で作成します。
+#[derive(Debug)]
+pub struct DeltaEncode<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    prev: u8,
+}
+
+/// 差分を累積して元のバイトに戻すイテレーター
+/// 
+/// [`BufBytes::delta_decode`]で作成します。
+#[derive(Debug)]
+pub struct DeltaDecode<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    prev: u8,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 各バイトを、前のバイトとの差分にするイテレーターにする
+    /// 
+    /// 最初のバイトはそのまま返します。 差分はwrappingで計算します。  
+    /// ゆっくり変化するデータを、圧縮しやすくする前処理に使えます。
+    pub fn delta_encode(self) -> DeltaEncode<B> {
+        DeltaEncode { inner: self, prev: 0 }
+    }
+
+    /// 差分を累積して、元のバイトに戻すイテレーターにする
+    /// 
+    /// [`delta_encode`](Self::delta_encode)の逆変換です。
+    pub fn delta_decode(self) -> DeltaDecode<B> {
+        DeltaDecode { inner: self, prev: 0 }
+    }
+}
+
+impl<B> Iterator for DeltaEncode<B>
+where
+    B: Read,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // prevの初期値が0なので、最初のバイトはそのままになる
+        let byte = self.inner.next()?;
+        let delta = byte.wrapping_sub(self.prev);
+        self.prev = byte;
+        Some(delta)
+    }
+}
+
+impl<B> Iterator for DeltaDecode<B>
+where
+    B: Read,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.prev = self.prev.wrapping_add(self.inner.next()?);
+        Some(self.prev)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    #[test]
+    fn delta_encode_test() {
+        let base = [10, 12, 15, 15, 9, 0, 0xff];
+        let res: Vec<u8> = BufBytes::with_capacity(&base[..], 3).unwrap().delta_encode().collect();
+        assert_eq!(res, [10, 2, 3, 0, 0xfa, 0xf7, 0xff]);
+    }
+
+    // encodeしてdecodeすると元に戻る
+    #[test]
+    fn delta_round_trip_test() {
+        let base: Vec<u8> = (0..1000u32).map(|i| (i * i % 251) as u8).collect();
+        let delta: Vec<u8> = BufBytes::with_capacity(&base[..], 7).unwrap().delta_encode().collect();
+        let res: Vec<u8> = BufBytes::with_capacity(&delta[..], 7).unwrap().delta_decode().collect();
+        assert_eq!(res, base);
+    }
+}
@@ -0,0 +1,98 @@
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// factorバイトごとの平均を返すイテレーター
+/// 
+/// [`BufBytes::downsample`]で作成します。
+#[derive(Debug)]
+pub struct Downsample<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    factor: usize,
+    drop_tail: bool,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// factorバイトごとの平均(四捨五入)を、1バイトにして返すイテレーターにする
+    /// 
+    /// 簡易的なダウンサンプリングに使えます。  
+    /// 最後にfactorバイトに満たない端数が残ったときは、残った分だけの平均を返します。  
+    /// 端数を捨てたいときは[`Downsample::drop_tail`]を使ってください。
+    /// 
+    /// # Panics
+    /// factorが0のときはpanicします。
+    pub fn downsample(self, factor: usize) -> Downsample<B> {
+        assert!(factor != 0, "factor must be non-zero");
+        Downsample { inner: self, factor, drop_tail: false }
+    }
+}
+
+impl<B> Downsample<B>
+where
+    B: Read,
+{
+    /// 最後のfactorバイトに満たない端数を捨てる
+    pub fn drop_tail(mut self) -> Self {
+        self.drop_tail = true;
+        self
+    }
+}
+
+impl<B> Iterator for Downsample<B>
+where
+    B: Read,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut sum = 0u64;
+        let mut count = 0;
+        // バッファ単位で、グループの分だけ足し込む
+        while count < self.factor {
+            if self.inner.buffer().is_empty() && !self.inner.refill_buffer() {
+                break;
+            }
+            let buf = self.inner.buffer();
+            let n = buf.len().min(self.factor - count);
+            sum += buf[..n].iter().map(|&b| b as u64).sum::<u64>();
+            self.inner.consume(n);
+            count += n;
+        }
+        if count == 0 || (count < self.factor && self.drop_tail) {
+            return None;
+        }
+        let count = count as u64;
+        Some(((sum + count / 2) / count) as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    // 3byteバッファの境界をまたぐ4byteのグループも平均できる
+    #[test]
+    fn downsample_test() {
+        let base = [0, 10, 20, 30, 100, 100, 101, 101, 255, 255, 255, 255];
+        let res: Vec<u8> = BufBytes::with_capacity(&base[..], 3).unwrap().downsample(4).collect();
+        // 15, 100.5(四捨五入), 255
+        assert_eq!(res, [15, 101, 255]);
+    }
+
+    // 端数は平均するか捨てるかを選べる
+    #[test]
+    fn downsample_tail_test() {
+        let base = [2, 4, 6, 8, 1, 2];
+        let res: Vec<u8> = BufBytes::new(&base[..]).unwrap().downsample(4).collect();
+        assert_eq!(res, [5, 2]);
+
+        let res: Vec<u8> = BufBytes::new(&base[..]).unwrap().downsample(4).drop_tail().collect();
+        assert_eq!(res, [5]);
+    }
+}
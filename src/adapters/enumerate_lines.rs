@@ -0,0 +1,78 @@
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// 行番号付きでバイトを返すイテレーター
+/// 
+/// [`BufBytes::enumerate_lines`]で作成します。
+#[derive(Debug)]
+pub struct EnumerateLines<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    line: usize,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// (行番号, バイト)のペアを返すイテレーターに変換する
+    /// 
+    /// 行番号は**1始まり**です。  
+    /// `\n`はその行の最後のバイトとして扱い、次のバイトから行番号が増えます。
+    pub fn enumerate_lines(self) -> EnumerateLines<B> {
+        EnumerateLines { inner: self, line: 1 }
+    }
+}
+
+impl<B> Iterator for EnumerateLines<B>
+where
+    B: Read,
+{
+    type Item = (usize, u8);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let byte = self.inner.next()?;
+        let line = self.line;
+        if byte == b'\n' {
+            self.line += 1;
+        }
+        Some((line, byte))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    // 行番号は1始まりで、\nはその行に含まれる
+    #[test]
+    fn enumerate_lines_test() {
+        let base = b"ab\ncd\n\ne";
+        // バッファ境界をまたぐように3byteバッファで読む
+        let bytes = BufBytes::with_capacity(&base[..], 3).unwrap();
+
+        let res: Vec<(usize, u8)> = bytes.enumerate_lines().collect();
+        assert_eq!(res, vec![
+            (1, b'a'), (1, b'b'), (1, b'\n'),
+            (2, b'c'), (2, b'd'), (2, b'\n'),
+            (3, b'\n'),
+            (4, b'e'),
+        ]);
+    }
+
+    // 特定の行だけ取り出す
+    #[test]
+    fn enumerate_lines_filter_test() {
+        let base = b"first\nsecond\nthird\n";
+        let bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        let line2: Vec<u8> = bytes.enumerate_lines()
+            .filter(|(line, _)| *line == 2)
+            .map(|(_, b)| b)
+            .collect();
+        assert_eq!(line2, b"second\n");
+    }
+}
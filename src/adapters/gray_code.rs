@@ -0,0 +1,92 @@
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// バイトをグレイコードにして返すイテレーター
+/// 
+/// [`BufBytes::to_gray_code`]で作成します。
+#[derive(Debug)]
+pub struct GrayCode<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+}
+
+/// グレイコードのバイトを2進数に戻して返すイテレーター
+/// 
+/// [`BufBytes::from_gray_code`]で作成します。
+#[derive(Debug)]
+pub struct FromGrayCode<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 各バイトをグレイコード(`n ^ (n >> 1)`)にするイテレーターにする
+    pub fn to_gray_code(self) -> GrayCode<B> {
+        GrayCode { inner: self }
+    }
+
+    /// グレイコードの各バイトを2進数に戻すイテレーターにする
+    /// 
+    /// [`to_gray_code`](Self::to_gray_code)の逆変換です。
+    pub fn from_gray_code(self) -> FromGrayCode<B> {
+        FromGrayCode { inner: self }
+    }
+}
+
+impl<B> Iterator for GrayCode<B>
+where
+    B: Read,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|n| n ^ (n >> 1))
+    }
+}
+
+impl<B> Iterator for FromGrayCode<B>
+where
+    B: Read,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // 上位のビットから順にXORを累積する
+        self.inner.next().map(|g| {
+            let mut n = g;
+            n ^= n >> 1;
+            n ^= n >> 2;
+            n ^= n >> 4;
+            n
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    #[test]
+    fn to_gray_code_test() {
+        let base = [0, 1, 2, 3, 4, 0xff];
+        let res: Vec<u8> = BufBytes::with_capacity(&base[..], 4).unwrap().to_gray_code().collect();
+        assert_eq!(res, [0, 1, 3, 2, 6, 0x80]);
+    }
+
+    // 全バイトでラウンドトリップする
+    #[test]
+    fn gray_code_round_trip_test() {
+        let base: Vec<u8> = (0..=255).collect();
+        let gray: Vec<u8> = BufBytes::with_capacity(&base[..], 7).unwrap().to_gray_code().collect();
+        let res: Vec<u8> = BufBytes::with_capacity(&gray[..], 7).unwrap().from_gray_code().collect();
+        assert_eq!(res, base);
+    }
+}
@@ -0,0 +1,97 @@
+use std::io::{Read, Result};
+
+use crate::{BufBytes, adapters::Lines, scan::pattern_position};
+
+/// patternを含む行だけを返すイテレーター
+/// 
+/// [`BufBytes::grep`]で作成します。
+#[derive(Debug)]
+pub struct Grep<B>
+where
+    B: Read,
+{
+    lines: Lines<B>,
+    pattern: Vec<u8>,
+    ignore_case: bool,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// patternを含む行だけを返すイテレーターにする
+    /// 
+    /// 行はバイト列のまま比べるので、UTF-8でない行も扱えます。 行末の`\n`と`\r\n`は取り除きます。  
+    /// 空のpatternは、すべての行に一致します。  
+    /// io処理でエラーが起きたら、そのエラーを一度返して終わります。
+    pub fn grep(self, pattern: Vec<u8>) -> Grep<B> {
+        Grep { lines: self.lines(), pattern, ignore_case: false }
+    }
+}
+
+impl<B> Grep<B>
+where
+    B: Read,
+{
+    /// ASCIIの大文字小文字を区別せずに比べる
+    pub fn ignore_case(mut self) -> Self {
+        self.pattern.make_ascii_lowercase();
+        self.ignore_case = true;
+        self
+    }
+
+    fn is_match(&self, line: &[u8]) -> bool {
+        if self.pattern.is_empty() {
+            return true;
+        }
+        if self.ignore_case {
+            return pattern_position(&self.pattern, &line.to_ascii_lowercase()).is_some();
+        }
+        pattern_position(&self.pattern, line).is_some()
+    }
+}
+
+impl<B> Iterator for Grep<B>
+where
+    B: Read,
+{
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next_bytes()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            if self.is_match(&line) {
+                return Some(Ok(line));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    // 一致する行だけが返り、ほかの行は飛ばされる
+    #[test]
+    fn grep_test() {
+        let base = b"INFO start\nERROR disk full\r\nINFO retry\nerror: again\nERROR done";
+        let res: Vec<Vec<u8>> = BufBytes::with_capacity(&base[..], 5).unwrap()
+            .grep(b"ERROR".to_vec()).collect::<std::io::Result<_>>().unwrap();
+        assert_eq!(res, [b"ERROR disk full".to_vec(), b"ERROR done".to_vec()]);
+
+        let res: Vec<Vec<u8>> = BufBytes::with_capacity(&base[..], 5).unwrap()
+            .grep(b"nothing".to_vec()).collect::<std::io::Result<_>>().unwrap();
+        assert!(res.is_empty());
+    }
+
+    #[test]
+    fn grep_ignore_case_test() {
+        let base = b"INFO start\nERROR disk full\nINFO retry\nerror: again\n";
+        let res: Vec<Vec<u8>> = BufBytes::with_capacity(&base[..], 5).unwrap()
+            .grep(b"Error".to_vec()).ignore_case().collect::<std::io::Result<_>>().unwrap();
+        assert_eq!(res, [b"ERROR disk full".to_vec(), b"error: again".to_vec()]);
+    }
+}
@@ -0,0 +1,143 @@
+use std::io::{Error, ErrorKind, Read, Result};
+
+use crate::BufBytes;
+
+/// バイトを16進の2文字にして返すイテレーター
+/// 
+/// [`BufBytes::hex_encode`]で作成します。
+#[derive(Debug)]
+pub struct HexEncode<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    digits: &'static [u8; 16],
+    // まだ返していない下位4bitの文字
+    low: Option<u8>,
+}
+
+/// 16進の2文字を1バイトに戻して返すイテレーター
+/// 
+/// [`BufBytes::hex_decode`]で作成します。
+#[derive(Debug)]
+pub struct HexDecode<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    done: bool,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 各バイトを16進の2文字(asciiのバイト)にするイテレーターにする
+    /// 
+    /// uppercaseが`true`なら`A`〜`F`、`false`なら`a`〜`f`を使います。  
+    /// 16進ダンプやデバッグ出力に使えます。 元に戻すときは[`hex_decode`](Self::hex_decode)を使います。
+    pub fn hex_encode(self, uppercase: bool) -> HexEncode<B> {
+        let digits = if uppercase { b"0123456789ABCDEF" } else { b"0123456789abcdef" };
+        HexEncode { inner: self, digits, low: None }
+    }
+
+    /// 16進の2文字ずつを1バイトに戻すイテレーターにする
+    /// 
+    /// 大文字と小文字はどちらでも読めます。  
+    /// 16進でない文字は`InvalidData`を、最後に1文字余ったときは`UnexpectedEof`を、  
+    /// io処理でエラーが起きたときはそのエラーを、一度返して終わります。
+    pub fn hex_decode(self) -> HexDecode<B> {
+        HexDecode { inner: self, done: false }
+    }
+}
+
+impl<B> Iterator for HexEncode<B>
+where
+    B: Read,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(low) = self.low.take() {
+            return Some(low);
+        }
+        let byte = self.inner.next()?;
+        self.low = Some(self.digits[(byte & 0x0f) as usize]);
+        Some(self.digits[(byte >> 4) as usize])
+    }
+}
+
+impl<B> HexDecode<B>
+where
+    B: Read,
+{
+    /// 16進の1文字を4bitの値にする
+    fn nibble(c: u8) -> Result<u8> {
+        (c as char).to_digit(16)
+            .map(|v| v as u8)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("invalid hex digit: {:#04x}", c)))
+    }
+}
+
+impl<B> Iterator for HexDecode<B>
+where
+    B: Read,
+{
+    type Item = Result<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut pair = [0; 2];
+        let res = match self.inner.fill_slice(&mut pair) {
+            0 if self.inner.error.is_none() => return None,
+            2 => Self::nibble(pair[0]).and_then(|high| Ok(high << 4 | Self::nibble(pair[1])?)),
+            _ => Err(self.inner.short_read_error()),
+        };
+        self.done = res.is_err();
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::BufBytes;
+
+    #[test]
+    fn hex_encode_test() {
+        let base = [0x00, 0x7f, 0xab, 0xff];
+        let lower: Vec<u8> = BufBytes::with_capacity(&base[..], 3).unwrap().hex_encode(false).collect();
+        assert_eq!(lower, b"007fabff");
+        let upper: Vec<u8> = BufBytes::with_capacity(&base[..], 3).unwrap().hex_encode(true).collect();
+        assert_eq!(upper, b"007FABFF");
+    }
+
+    // encodeしてdecodeすれば元に戻る
+    #[test]
+    fn hex_round_trip_test() {
+        let base: Vec<u8> = (0..=255).collect();
+        for uppercase in [false, true] {
+            let encoded: Vec<u8> = BufBytes::with_capacity(&base[..], 7).unwrap().hex_encode(uppercase).collect();
+            let decoded: Vec<u8> = BufBytes::with_capacity(&encoded[..], 7).unwrap()
+                .hex_decode()
+                .map(|b| b.unwrap())
+                .collect();
+            assert_eq!(decoded, base);
+        }
+    }
+
+    // 16進でない文字や、余った1文字はエラー
+    #[test]
+    fn hex_decode_error_test() {
+        let mut iter = BufBytes::new(&b"4gff"[..]).unwrap().hex_decode();
+        assert_eq!(iter.next().unwrap().unwrap_err().kind(), ErrorKind::InvalidData);
+        assert!(iter.next().is_none());
+
+        let mut iter = BufBytes::new(&b"41f"[..]).unwrap().hex_decode();
+        assert_eq!(iter.next().unwrap().unwrap(), b'A');
+        assert_eq!(iter.next().unwrap().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+}
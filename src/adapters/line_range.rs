@@ -0,0 +1,103 @@
+use std::io::{Read, Result};
+
+use crate::{BufBytes, adapters::Lines};
+
+/// start行目からend行目までを返すイテレーター
+/// 
+/// [`BufBytes::line_range`]で作成します。
+#[derive(Debug)]
+pub struct LineRange<B>
+where
+    B: Read,
+{
+    lines: Lines<B>,
+    // 次に読む行の行番号
+    line: usize,
+    start: usize,
+    end: usize,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// start行目からend行目まで(endを含む)の行だけを返すイテレーターにする
+    /// 
+    /// 行番号は**1始まり**です。 行末の`\n`と`\r\n`は取り除きます。  
+    /// start行目より前の行は、バッファの中を探して読み飛ばします。  
+    /// end行目を返したらそこで終わり、残りは読みません。 ファイルの行数がendに満たなければ、最後の行までです。  
+    /// io処理でエラーが起きたら、そのエラーを一度返して終わります。
+    /// 
+    /// # Panics
+    /// startが0のときはpanicします。
+    pub fn line_range(self, start: usize, end: usize) -> LineRange<B> {
+        assert!(start != 0, "line numbers start at 1");
+        LineRange { lines: self.lines(), line: 1, start, end }
+    }
+}
+
+impl<B> Iterator for LineRange<B>
+where
+    B: Read,
+{
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.line > self.end || self.start > self.end {
+            return None;
+        }
+        while self.line < self.start {
+            if let Err(e) = self.lines.skip_line()? {
+                return Some(Err(e));
+            }
+            self.line += 1;
+        }
+        let line = self.lines.next_bytes()?;
+        self.line += 1;
+        Some(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use crate::BufBytes;
+
+    // 範囲を過ぎたら読まないように、読んだらpanicする仮想ファイル
+    struct Unreachable;
+
+    impl Read for Unreachable {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            panic!("read past the range");
+        }
+    }
+
+    fn lines(n: usize) -> Vec<u8> {
+        (1..=n).flat_map(|i| format!("line {i}\n").into_bytes()).collect()
+    }
+
+    // 範囲の行だけが返り、その先は読まない
+    #[test]
+    fn line_range_test() {
+        let base = lines(300);
+        let bytes = BufBytes::with_capacity((&base[..]).chain(Unreachable), 16).unwrap();
+        let res: Vec<Vec<u8>> = bytes.line_range(100, 200).collect::<std::io::Result<_>>().unwrap();
+        assert_eq!(res.len(), 101);
+        assert_eq!(res[0], b"line 100");
+        assert_eq!(res[100], b"line 200");
+    }
+
+    // ファイルの行数を超えたら、あるところまで
+    #[test]
+    fn line_range_over_test() {
+        let base = b"a\r\nb\nc";
+        let res: Vec<Vec<u8>> = BufBytes::new(&base[..]).unwrap().line_range(2, 10).collect::<std::io::Result<_>>().unwrap();
+        assert_eq!(res, [b"b".to_vec(), b"c".to_vec()]);
+
+        let res: Vec<Vec<u8>> = BufBytes::new(&base[..]).unwrap().line_range(5, 10).collect::<std::io::Result<_>>().unwrap();
+        assert!(res.is_empty());
+        // startがendより後なら空
+        assert_eq!(BufBytes::new(&base[..]).unwrap().line_range(3, 2).count(), 0);
+    }
+}
@@ -0,0 +1,198 @@
+use std::io::{Error, ErrorKind, Read, Result};
+
+use crate::BufBytes;
+
+/// 1行ずつ`String`を返すイテレーター
+/// 
+/// [`BufBytes::lines`]で作成します。
+#[derive(Debug)]
+pub struct Lines<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    done: bool,
+}
+
+/// 前後の空白を取り除いた行を返すイテレーター
+/// 
+/// [`BufBytes::trimmed_lines`]で作成します。
+#[derive(Debug)]
+pub struct TrimmedLines<B>
+where
+    B: Read,
+{
+    lines: Lines<B>,
+    skip_empty: bool,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 1行ずつ`String`を返すイテレーターにする
+    /// 
+    /// 行末の`\n`と`\r\n`は取り除きます。  
+    /// UTF-8として不正な行は`InvalidData`になります。  
+    /// io処理でエラーが起きたら、そのエラーを一度返して終わります。
+    pub fn lines(self) -> Lines<B> {
+        Lines { inner: self, done: false }
+    }
+
+    /// 各行の前後の空白を取り除いて返すイテレーターにする
+    /// 
+    /// [`lines`](Self::lines)の各行に`str::trim`をかけたものです。  
+    /// 空になった行も返すので、飛ばしたいときは[`TrimmedLines::skip_empty`]を使ってください。
+    pub fn trimmed_lines(self) -> TrimmedLines<B> {
+        TrimmedLines { lines: self.lines(), skip_empty: false }
+    }
+}
+
+impl<B> Lines<B>
+where
+    B: Read,
+{
+    /// 次の行を、行末の`\n`と`\r\n`を取り除いたバイト列のまま返す
+    /// 
+    /// [`Grep`](crate::adapters::Grep)のように、UTF-8でない行も扱うアダプタはこちらで読む
+    pub(super) fn next_bytes(&mut self) -> Option<Result<Vec<u8>>> {
+        if self.done {
+            return None;
+        }
+        let mut line = Vec::new();
+        match self.inner.read_until(b'\n', &mut line) {
+            Ok(0) => {
+                self.done = true;
+                return None;
+            },
+            Ok(_) => {},
+            Err(e) => {
+                self.done = true;
+                return Some(Err(e));
+            },
+        }
+        if line.ends_with(b"\n") {
+            line.pop();
+            if line.ends_with(b"\r") {
+                line.pop();
+            }
+        }
+        Some(Ok(line))
+    }
+
+    /// 次の行を、コピーせずにバッファの中を探して読み飛ばす
+    /// 
+    /// [`next_bytes`](Self::next_bytes)と同じく、EOFなら`None`、io処理のエラーならそのエラーを一度返して終わる
+    pub(super) fn skip_line(&mut self) -> Option<Result<()>> {
+        if self.done {
+            return None;
+        }
+        match self.inner.find_byte(b'\n') {
+            Ok(Some(_)) => {
+                self.inner.consume(1);
+                Some(Ok(()))
+            },
+            Ok(None) => {
+                self.done = true;
+                None
+            },
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            },
+        }
+    }
+}
+
+impl<B> TrimmedLines<B>
+where
+    B: Read,
+{
+    /// トリムして空になった行を飛ばす
+    pub fn skip_empty(mut self) -> Self {
+        self.skip_empty = true;
+        self
+    }
+}
+
+impl<B> Iterator for Lines<B>
+where
+    B: Read,
+{
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = match self.next_bytes()? {
+            Ok(line) => line,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(String::from_utf8(line).map_err(|e| Error::new(ErrorKind::InvalidData, e)))
+    }
+}
+
+impl<B> Iterator for TrimmedLines<B>
+where
+    B: Read,
+{
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            let trimmed = line.trim();
+            if self.skip_empty && trimmed.is_empty() {
+                continue;
+            }
+            return Some(Ok(trimmed.to_string()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::BufBytes;
+
+    #[test]
+    fn lines_test() {
+        let base = b"first\nsecond\r\n\nlast";
+        let bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        let res: Vec<String> = bytes.lines().map(|l| l.unwrap()).collect();
+        assert_eq!(res, vec!["first", "second", "", "last"]);
+    }
+
+    #[test]
+    fn lines_invalid_utf8_test() {
+        let base = b"ok\n\xff\xfe\nok";
+        let bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        let res: Vec<_> = bytes.lines().collect();
+        assert_eq!(res.len(), 3);
+        assert_eq!(res[1].as_ref().unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    // 前後のタブ・空白がトリムされる
+    #[test]
+    fn trimmed_lines_test() {
+        let base = b"  key = value\t\n\t\n\tname=foo  \r\n";
+        let bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        let res: Vec<String> = bytes.trimmed_lines().map(|l| l.unwrap()).collect();
+        assert_eq!(res, vec!["key = value", "", "name=foo"]);
+    }
+
+    // 空行スキップ
+    #[test]
+    fn trimmed_lines_skip_empty_test() {
+        let base = b"\n  a\n \t \n\nb  \n   ";
+        let bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        let res: Vec<String> = bytes.trimmed_lines().skip_empty().map(|l| l.unwrap()).collect();
+        assert_eq!(res, vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,116 @@
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// 各バイトに関数をかけて返すイテレーター
+/// 
+/// [`BufBytes::map_bytes`]で作成します。  
+/// `Iterator::map`と違い、[`get_err`](Self::get_err)や[`position`](Self::position)をそのまま使えます。
+pub struct MapBytes<B, F>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    f: F,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 各バイトにfをかけるイテレーターにする
+    /// 
+    /// ROT13やバイトの置換などに使えます。  
+    /// `fold`はバッファ単位でまとめて処理するので、`Iterator::map`より速くなります。
+    pub fn map_bytes<F>(self, f: F) -> MapBytes<B, F>
+    where
+        F: FnMut(u8) -> u8,
+    {
+        MapBytes { inner: self, f }
+    }
+}
+
+impl<B, F> MapBytes<B, F>
+where
+    B: Read,
+{
+    /// io操作中に生じたエラーを取得する
+    /// 
+    /// [`BufBytes::get_err`]と同じです。
+    pub fn get_err(&self) -> &Option<std::io::Error> {
+        self.inner.get_err()
+    }
+
+    /// 読み取り位置(これまでに返したバイト数)を返す
+    /// 
+    /// [`BufBytes::position`]と同じです。
+    pub fn position(&self) -> u64 {
+        BufBytes::position(&self.inner)
+    }
+}
+
+impl<B, F> Iterator for MapBytes<B, F>
+where
+    B: Read,
+    F: FnMut(u8) -> u8,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(&mut self.f)
+    }
+
+    fn fold<Acc, G>(mut self, init: Acc, mut g: G) -> Acc
+    where
+        G: FnMut(Acc, Self::Item) -> Acc,
+    {
+        let f = &mut self.f;
+        self.inner.fold_chunks(init, |acc, buf| buf.iter().fold(acc, |acc, &b| g(acc, f(b))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Error, Read, Result};
+
+    use crate::BufBytes;
+
+    fn rot13(b: u8) -> u8 {
+        match b {
+            b'a'..=b'z' => (b - b'a' + 13) % 26 + b'a',
+            b'A'..=b'Z' => (b - b'A' + 13) % 26 + b'A',
+            _ => b,
+        }
+    }
+
+    // nextでもfoldでも、全バイトに変換がかかる
+    #[test]
+    fn map_bytes_test() {
+        let base = b"Hello, World!";
+        let mut iter = BufBytes::with_capacity(&base[..], 4).unwrap().map_bytes(rot13);
+        assert_eq!(iter.next(), Some(b'U'));
+        assert_eq!(iter.position(), 1);
+        let rest: Vec<u8> = iter.collect();
+        assert_eq!(rest, b"ryyb, Jbeyq!");
+
+        let res = BufBytes::with_capacity(&base[..], 4).unwrap()
+            .map_bytes(rot13)
+            .fold(Vec::new(), |mut acc, b| { acc.push(b); acc });
+        assert_eq!(res, b"Uryyb, Jbeyq!");
+    }
+
+    // ラッパーからエラーが取れる
+    #[test]
+    fn map_bytes_error_test() {
+        struct Broken;
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> Result<usize> {
+                Err(Error::other("broken"))
+            }
+        }
+        let base = (&b"abc"[..]).chain(Broken);
+        let mut iter = BufBytes::with_capacity(base, 2).unwrap().map_bytes(|b| b ^ 0x20);
+        assert_eq!(iter.by_ref().count(), 3);
+        assert!(iter.get_err().is_some());
+    }
+}
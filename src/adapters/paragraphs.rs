@@ -0,0 +1,98 @@
+use std::io::{Error, ErrorKind, Read, Result};
+
+use crate::BufBytes;
+
+/// 空行で区切った段落を返すイテレーター
+/// 
+/// [`BufBytes::paragraphs`]で作成します。
+#[derive(Debug)]
+pub struct Paragraphs<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    done: bool,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 空行で区切った段落ごとに、`String`を返すイテレーターにする
+    /// 
+    /// 連続する空でない行を、`\n`でつないで1つの段落にします。(最後の行の改行は含みません)  
+    /// 先頭・末尾の空行や、連続する空行は無視するので、空の段落は返しません。  
+    /// 行末の`\r`は取り除きます。  
+    /// UTF-8として不正な段落は`InvalidData`になります。
+    pub fn paragraphs(self) -> Paragraphs<B> {
+        Paragraphs { inner: self, done: false }
+    }
+}
+
+impl<B> Iterator for Paragraphs<B>
+where
+    B: Read,
+{
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut paragraph = Vec::new();
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match self.inner.read_until(b'\n', &mut line) {
+                Ok(0) => break,
+                Ok(_) => {},
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                },
+            }
+            let trimmed = line.strip_suffix(b"\n").unwrap_or(&line);
+            let trimmed = trimmed.strip_suffix(b"\r").unwrap_or(trimmed);
+            if trimmed.is_empty() {
+                // 空行は段落の区切り。 まだ段落が始まっていなければ読み飛ばす
+                if paragraph.is_empty() {
+                    continue;
+                }
+                break;
+            }
+            if !paragraph.is_empty() {
+                paragraph.push(b'\n');
+            }
+            paragraph.extend_from_slice(trimmed);
+        }
+        if paragraph.is_empty() {
+            self.done = true;
+            return None;
+        }
+        Some(String::from_utf8(paragraph).map_err(|e| Error::new(ErrorKind::InvalidData, e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    #[test]
+    fn paragraphs_test() {
+        let base = b"line1\nline2\n\nline3\r\n\r\nline4\nline5";
+        let bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        let res: Vec<String> = bytes.paragraphs().map(|p| p.unwrap()).collect();
+        assert_eq!(res, vec!["line1\nline2", "line3", "line4\nline5"]);
+    }
+
+    // 先頭・末尾の空行や、連続する空行で空の段落を作らない
+    #[test]
+    fn paragraphs_many_blank_lines_test() {
+        let base = b"\n\n\nfirst\n\n\n\n\nsecond\n\n\n";
+        let bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        let res: Vec<String> = bytes.paragraphs().map(|p| p.unwrap()).collect();
+        assert_eq!(res, vec!["first", "second"]);
+    }
+}
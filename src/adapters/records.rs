@@ -0,0 +1,119 @@
+use std::{io::{Read, Result}, marker::PhantomData};
+
+use crate::BufBytes;
+
+/// 固定長レコードをパースして返すイテレーター
+/// 
+/// [`BufBytes::records`]で作成します。
+pub struct Records<B, T, F>
+where
+    B: Read,
+    F: FnMut(&[u8]) -> Result<T>,
+{
+    inner: BufBytes<B>,
+    record_size: usize,
+    parse: F,
+    done: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// record_sizeバイトずつ区切って、parseで変換するイテレーターにする
+    /// 
+    /// parseにはバッファの中身をそのまま渡すので、コピーは起きません。  
+    /// 最後にrecord_sizeに満たない端数が残ったときは`UnexpectedEof`を、  
+    /// io処理でエラーが起きたときはそのエラーを、一度返して終わります。
+    /// 
+    /// # Panics
+    /// record_sizeが0のときはpanicします。
+    pub fn records<T, F>(self, record_size: usize, parse: F) -> Records<B, T, F>
+    where
+        F: FnMut(&[u8]) -> Result<T>,
+    {
+        assert!(record_size != 0, "record_size must be non-zero");
+        Records { inner: self, record_size, parse, done: false, _marker: PhantomData }
+    }
+}
+
+impl<B, T, F> Iterator for Records<B, T, F>
+where
+    B: Read,
+    F: FnMut(&[u8]) -> Result<T>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let record = match self.inner.fill_buf_at_least(self.record_size) {
+            Ok(buf) => &buf[..self.record_size],
+            Err(e) => {
+                // 端数やエラーを返したら、それで終わり
+                self.done = true;
+                let rest = self.inner.buffer().len();
+                if rest == 0 && self.inner.error.is_none() {
+                    return None;
+                }
+                self.inner.consume(rest);
+                return Some(Err(e));
+            },
+        };
+        let res = (self.parse)(record);
+        self.inner.consume(self.record_size);
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::BufBytes;
+
+    #[derive(Debug, PartialEq)]
+    struct Record {
+        id: u16,
+        value: u32,
+    }
+
+    fn parse(buf: &[u8]) -> std::io::Result<Record> {
+        Ok(Record {
+            id: u16::from_le_bytes([buf[0], buf[1]]),
+            value: u32::from_le_bytes([buf[2], buf[3], buf[4], buf[5]]),
+        })
+    }
+
+    // 6byteのレコードを、4byteバッファで読む
+    #[test]
+    fn records_test() {
+        let mut base = Vec::new();
+        for (id, value) in [(1u16, 100u32), (2, 200), (3, 300)] {
+            base.extend(id.to_le_bytes());
+            base.extend(value.to_le_bytes());
+        }
+        let bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        let res: Vec<Record> = bytes.records(6, parse).map(|r| r.unwrap()).collect();
+        assert_eq!(res, vec![
+            Record { id: 1, value: 100 },
+            Record { id: 2, value: 200 },
+            Record { id: 3, value: 300 },
+        ]);
+    }
+
+    // 中途半端なレコードはUnexpectedEof
+    #[test]
+    fn records_truncated_test() {
+        let base = [1, 0, 100, 0, 0, 0, 2, 0, 200];
+        let bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        let res: Vec<_> = bytes.records(6, parse).collect();
+        assert_eq!(res.len(), 2);
+        assert_eq!(*res[0].as_ref().unwrap(), Record { id: 1, value: 100 });
+        assert_eq!(res[1].as_ref().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+}
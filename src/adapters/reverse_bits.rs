@@ -0,0 +1,49 @@
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// バイトのビット順を逆にして返すイテレーター
+/// 
+/// [`BufBytes::reverse_bits_iter`]で作成します。
+#[derive(Debug)]
+pub struct ReverseBits<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 各バイトのビット順を逆にする(`u8::reverse_bits`)イテレーターにする
+    /// 
+    /// LSBから先に送るプロトコルのデータを、MSB先に直すのに使えます。
+    pub fn reverse_bits_iter(self) -> ReverseBits<B> {
+        ReverseBits { inner: self }
+    }
+}
+
+impl<B> Iterator for ReverseBits<B>
+where
+    B: Read,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(u8::reverse_bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    #[test]
+    fn reverse_bits_test() {
+        let base = [0b1000_0000, 0b0000_0001, 0b1100_1010, 0xff, 0];
+        let res: Vec<u8> = BufBytes::with_capacity(&base[..], 2).unwrap().reverse_bits_iter().collect();
+        assert_eq!(res, [0b0000_0001, 0b1000_0000, 0b0101_0011, 0xff, 0]);
+    }
+}
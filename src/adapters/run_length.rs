@@ -0,0 +1,81 @@
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// 連続する同じバイトをまとめて返すイテレーター
+/// 
+/// [`BufBytes::run_length`]で作成します。
+#[derive(Debug)]
+pub struct RunLength<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 連続する同じバイトを、(バイト, 連続回数)にまとめるイテレーターにする
+    /// 
+    /// バッファの境界をまたいで続くランも、分けずに1つにまとめます。
+    pub fn run_length(self) -> RunLength<B> {
+        RunLength { inner: self }
+    }
+}
+
+impl<B> Iterator for RunLength<B>
+where
+    B: Read,
+{
+    type Item = (u8, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let byte = self.inner.next()?;
+        let mut count = 1;
+        // バッファ単位で、同じバイトが続く分を数えて読み飛ばす
+        loop {
+            if self.inner.buffer().is_empty() && !self.inner.refill_buffer() {
+                break;
+            }
+            let buf = self.inner.buffer();
+            let n = buf.iter().take_while(|&&b| b == byte).count();
+            let end = n < buf.len();
+            self.inner.consume(n);
+            count += n;
+            if end {
+                break;
+            }
+        }
+        Some((byte, count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    // 全バイトが異なれば、各ランは長さ1
+    #[test]
+    fn run_length_distinct_test() {
+        let base: Vec<u8> = (0..20).collect();
+        let bytes = BufBytes::with_capacity(&base[..], 3).unwrap();
+
+        let res: Vec<(u8, usize)> = bytes.run_length().collect();
+        assert_eq!(res, base.iter().map(|&b| (b, 1)).collect::<Vec<_>>());
+    }
+
+    // 長いランがバッファをまたいでも1つにまとまる
+    #[test]
+    fn run_length_long_run_test() {
+        let mut base = vec![b'a'; 3];
+        base.extend([b'b'; 100]);
+        base.extend([b'a'; 5]);
+        base.push(b'c');
+        let bytes = BufBytes::with_capacity(&base[..], 8).unwrap();
+
+        let res: Vec<(u8, usize)> = bytes.run_length().collect();
+        assert_eq!(res, vec![(b'a', 3), (b'b', 100), (b'a', 5), (b'c', 1)]);
+    }
+}
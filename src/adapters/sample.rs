@@ -0,0 +1,95 @@
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// 各バイトを確率rateで採用するイテレーター
+/// 
+/// [`BufBytes::sample`]で作成します。
+#[derive(Debug)]
+pub struct Sample<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    rate: f64,
+    state: u64,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 各バイトを確率rateで採用し、残りを捨てるイテレーターにする
+    /// 
+    /// 乱数はseedから決まる疑似乱数(SplitMix64)なので、seedが同じなら毎回同じバイトが選ばれます。  
+    /// rateが1.0以上なら全バイト、0.0以下なら何も返しません。
+    pub fn sample(self, rate: f64, seed: u64) -> Sample<B> {
+        Sample { inner: self, rate, state: seed }
+    }
+}
+
+impl<B> Sample<B>
+where
+    B: Read,
+{
+    /// \[0, 1)の疑似乱数を返す
+    fn next_f64(&mut self) -> f64 {
+        // SplitMix64
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^= z >> 31;
+        // 上位53bitを仮数にする
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl<B> Iterator for Sample<B>
+where
+    B: Read,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let byte = self.inner.next()?;
+            if self.next_f64() < self.rate {
+                return Some(byte);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    // rate=1.0で全バイト、rate=0.0で何も返らない
+    #[test]
+    fn sample_rate_test() {
+        let base: Vec<u8> = (0..=255).collect();
+
+        let bytes = BufBytes::with_capacity(&base[..], 16).unwrap();
+        assert_eq!(bytes.sample(1.0, 42).collect::<Vec<u8>>(), base);
+
+        let bytes = BufBytes::with_capacity(&base[..], 16).unwrap();
+        assert_eq!(bytes.sample(0.0, 42).count(), 0);
+    }
+
+    // seedが同じなら同じバイトが選ばれ、違えば別のバイトが選ばれる
+    #[test]
+    fn sample_seed_test() {
+        let base: Vec<u8> = (0..=255).cycle().take(10000).collect();
+        let sample = |seed| {
+            let bytes = BufBytes::with_capacity(&base[..], 16).unwrap();
+            bytes.sample(0.1, seed).collect::<Vec<u8>>()
+        };
+
+        assert_eq!(sample(1), sample(1));
+        assert_ne!(sample(1), sample(2));
+        // おおよそrateの割合だけ選ばれる
+        let len = sample(1).len();
+        assert!((800..1200).contains(&len), "len = {}", len);
+    }
+}
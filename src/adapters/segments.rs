@@ -0,0 +1,102 @@
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// boundaryの直後で区切った、chunk_hintバイト程度のチャンクを返すイテレーター
+/// 
+/// [`BufBytes::segments`]で作成します。
+#[derive(Debug)]
+pub struct Segments<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    boundary: u8,
+    chunk_hint: usize,
+    done: bool,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// chunk_hintバイト程度ずつ、必ずboundaryの直後で区切った`Vec<u8>`を返すイテレーターにする
+    /// 
+    /// chunk_hintバイト読んだあと、次のboundaryまで読み足して1つのチャンクにします。  
+    /// 各チャンクは完結したレコードの集まりになるので、行を分断せずに並列でパースできます。  
+    /// 最後のチャンクだけは、boundaryで終わらないことがあります。  
+    /// io処理でエラーが起きたら、そこまでをチャンクとして返して終わります。(エラーは[`Segments::get_err`]で取れます)
+    /// 
+    /// # Panics
+    /// chunk_hintが0のときはpanicします。
+    pub fn segments(self, boundary: u8, chunk_hint: usize) -> Segments<B> {
+        assert!(chunk_hint != 0, "chunk_hint must be non-zero");
+        Segments { inner: self, boundary, chunk_hint, done: false }
+    }
+}
+
+impl<B> Segments<B>
+where
+    B: Read,
+{
+    /// io操作中に生じたエラーを取得する
+    /// 
+    /// [`BufBytes::get_err`]と同じです。
+    pub fn get_err(&self) -> &Option<std::io::Error> {
+        self.inner.get_err()
+    }
+}
+
+impl<B> Iterator for Segments<B>
+where
+    B: Read,
+{
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut chunk = vec![0; self.chunk_hint];
+        let len = self.inner.fill_slice(&mut chunk);
+        chunk.truncate(len);
+        // ちょうどboundaryで終わっていなければ、次のboundaryまで読み足す
+        if len == self.chunk_hint && chunk.last() != Some(&self.boundary) {
+            let _ = self.inner.read_until(self.boundary, &mut chunk);
+        }
+        if self.inner.error.is_some() {
+            self.done = true;
+        }
+        if chunk.is_empty() {
+            self.done = true;
+            return None;
+        }
+        Some(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    // どのチャンクも行の途中で切れず、つなげると元に戻る
+    #[test]
+    fn segments_test() {
+        let base: Vec<u8> = (0..200).flat_map(|i| format!("line {i}\n").into_bytes()).collect();
+        for hint in [1, 7, 64, 10000] {
+            let chunks: Vec<Vec<u8>> = BufBytes::with_capacity(&base[..], 16).unwrap().segments(b'\n', hint).collect();
+            assert!(chunks.iter().all(|c| c.ends_with(b"\n")));
+            // 最後以外は、chunk_hintバイト以上ある
+            assert!(chunks[..chunks.len() - 1].iter().all(|c| c.len() >= hint));
+            assert_eq!(chunks.concat(), base);
+        }
+    }
+
+    // 最後のチャンクは、boundaryで終わらなくても返す
+    #[test]
+    fn segments_tail_test() {
+        let base = b"aaa\nbbbbbb\ncc";
+        let chunks: Vec<Vec<u8>> = BufBytes::with_capacity(&base[..], 4).unwrap().segments(b'\n', 2).collect();
+        assert_eq!(chunks, [b"aaa\n".to_vec(), b"bbbbbb\n".to_vec(), b"cc".to_vec()]);
+    }
+}
@@ -0,0 +1,138 @@
+use std::io::{Read, Result};
+
+use crate::BufBytes;
+
+/// 標準の[`std::io::Bytes`]と同じく、`Result<u8>`を返すイテレーター
+/// 
+/// [`BufBytes::into_std_bytes`]で作成します。
+#[derive(Debug)]
+pub struct StdBytesCompat<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    done: bool,
+}
+
+/// `Result<u8>`のイテレーターを、`Read`として読めるようにしたもの
+/// 
+/// [`BufBytes::from_std_bytes`]のbaseになります。
+#[derive(Debug)]
+pub struct StdBytesReader<I> {
+    iter: I,
+    // バイトを返したあとに出たエラーは、次のreadで返す
+    pending: Option<std::io::Error>,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 標準の`Read::bytes()`と同じく、`Result<u8>`を返すイテレーターにする
+    /// 
+    /// io処理でエラーが起きたときは、そのエラーを一度返して終わります。  
+    /// `Iterator<Item = io::Result<u8>>`を受け取る既存のコードに渡すのに使えます。
+    pub fn into_std_bytes(self) -> StdBytesCompat<B> {
+        StdBytesCompat { inner: self, done: false }
+    }
+}
+
+impl<I> BufBytes<StdBytesReader<I>>
+where
+    I: Iterator<Item = Result<u8>>,
+{
+    /// 標準の[`std::io::Bytes`]などの`Result<u8>`のイテレーターから、BufBytesを作成
+    /// 
+    /// イテレーターからsizeバイトずつ取り出して、バッファに貯めます。  
+    /// [`with_capacity`](Self::with_capacity)と同じく、空のイテレーターは`ErrorKind::UnexpectedEof`のエラーになります。
+    pub fn from_std_bytes(bytes: I, size: usize) -> Result<Self> {
+        Self::with_capacity(StdBytesReader { iter: bytes, pending: None }, size)
+    }
+}
+
+impl<B> Iterator for StdBytesCompat<B>
+where
+    B: Read,
+{
+    type Item = Result<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if let Some(byte) = self.inner.next() {
+            return Some(Ok(byte));
+        }
+        self.done = true;
+        self.inner.error.take().map(Err)
+    }
+}
+
+impl<I> Read for StdBytesReader<I>
+where
+    I: Iterator<Item = Result<u8>>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if let Some(e) = self.pending.take() {
+            return Err(e);
+        }
+        let mut n = 0;
+        while n < buf.len() {
+            match self.iter.next() {
+                Some(Ok(byte)) => {
+                    buf[n] = byte;
+                    n += 1;
+                },
+                Some(Err(e)) if n == 0 => return Err(e),
+                Some(Err(e)) => {
+                    self.pending = Some(e);
+                    break;
+                },
+                None => break,
+            }
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Error, ErrorKind, Read};
+
+    use crate::BufBytes;
+
+    // 標準のbytes()と同じバイト列を返す
+    #[test]
+    fn into_std_bytes_test() {
+        let base: Vec<u8> = (0..=255).collect();
+        let res: Vec<u8> = BufBytes::with_capacity(&base[..], 7).unwrap()
+            .into_std_bytes().collect::<std::io::Result<_>>().unwrap();
+        let expected: Vec<u8> = (&base[..]).bytes().collect::<std::io::Result<_>>().unwrap();
+        assert_eq!(res, expected);
+    }
+
+    // 標準のbytes()から作っても、同じバイト列を返す
+    #[test]
+    fn from_std_bytes_test() {
+        let base: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        let res: Vec<u8> = BufBytes::from_std_bytes((&base[..]).bytes(), 64).unwrap().collect();
+        assert_eq!(res, base);
+        // 往復しても変わらない
+        let res: Vec<u8> = BufBytes::from_std_bytes(BufBytes::new(&base[..]).unwrap().into_std_bytes(), 5).unwrap().collect();
+        assert_eq!(res, base);
+    }
+
+    // 途中のエラーは、それまでのバイトを返してから伝わる
+    #[test]
+    fn std_bytes_error_test() {
+        let items = vec![Ok(1), Ok(2), Err(Error::other("broken")), Ok(3)];
+        let mut bytes = BufBytes::from_std_bytes(items.into_iter(), 8).unwrap();
+        assert_eq!(bytes.by_ref().collect::<Vec<u8>>(), [1, 2]);
+        assert!(bytes.get_err().is_some());
+
+        let items = vec![Ok(1), Err(Error::other("broken"))];
+        let res: Vec<Result<u8, ErrorKind>> = BufBytes::from_std_bytes(items.into_iter(), 8).unwrap()
+            .into_std_bytes().map(|r| r.map_err(|e| e.kind())).collect();
+        assert_eq!(res, [Ok(1), Err(ErrorKind::Other)]);
+    }
+}
@@ -0,0 +1,77 @@
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// stepバイトごとに1バイト返すイテレーター
+/// 
+/// [`BufBytes::step_by_fast`]で作成します。
+#[derive(Debug)]
+pub struct StepBy<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    step: usize,
+    first: bool,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 最初のバイトから、stepバイトごとに1バイト返すイテレーターにする
+    /// 
+    /// 返す値は`Iterator::step_by`と同じですが、間のバイトは`next()`せず、  
+    /// バッファのポインタをまとめて進めて読み飛ばします。
+    /// 
+    /// # Panics
+    /// stepが0のときはpanicします。
+    pub fn step_by_fast(self, step: usize) -> StepBy<B> {
+        assert!(step != 0, "step must be non-zero");
+        StepBy { inner: self, step, first: true }
+    }
+}
+
+impl<B> Iterator for StepBy<B>
+where
+    B: Read,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.first {
+            self.first = false;
+        } else {
+            let skip = self.step as u64 - 1;
+            if self.inner.skip_bytes(skip) < skip {
+                return None;
+            }
+        }
+        self.inner.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    // step 1は全バイト
+    #[test]
+    fn step_by_fast_one_test() {
+        let base: Vec<u8> = (0..50).collect();
+        let bytes = BufBytes::with_capacity(&base[..], 7).unwrap();
+
+        assert_eq!(bytes.step_by_fast(1).collect::<Vec<u8>>(), base);
+    }
+
+    // バッファより大きいstepも含めて、愚直実装(Iterator::step_by)と一致する
+    #[test]
+    fn step_by_fast_matches_step_by_test() {
+        let base: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        for step in [2, 3, 4, 7, 8, 13, 64, 999, 1000, 2000] {
+            let fast = BufBytes::with_capacity(&base[..], 8).unwrap().step_by_fast(step);
+            let naive = BufBytes::with_capacity(&base[..], 8).unwrap().step_by(step);
+            assert_eq!(fast.collect::<Vec<u8>>(), naive.collect::<Vec<u8>>(), "step = {}", step);
+        }
+    }
+}
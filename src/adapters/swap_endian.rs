@@ -0,0 +1,120 @@
+use std::io::{Error, ErrorKind, Read};
+
+use crate::BufBytes;
+
+/// word_sizeバイトごとにバイト順を逆にして返すイテレーター
+/// 
+/// [`BufBytes::swap_endian`]で作成します。
+#[derive(Debug)]
+pub struct SwapEndian<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    // 逆順にしたwordと、そのうち返したバイト数
+    word: Vec<u8>,
+    len: usize,
+    pos: usize,
+    error_on_tail: bool,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// word_sizeバイトごとにバイト順を逆にするイテレーターにする
+    /// 
+    /// ビッグエンディアンのファイルをリトルエンディアンに直す(またはその逆)のに使えます。  
+    /// 最後にword_sizeに満たない端数が残ったときは、そのまま返します。  
+    /// 端数をエラーにしたいときは[`SwapEndian::error_on_tail`]を使ってください。
+    /// 
+    /// # Panics
+    /// word_sizeが0のときはpanicします。
+    pub fn swap_endian(self, word_size: usize) -> SwapEndian<B> {
+        assert!(word_size != 0, "word_size must be non-zero");
+        SwapEndian { inner: self, word: vec![0; word_size], len: 0, pos: 0, error_on_tail: false }
+    }
+}
+
+impl<B> SwapEndian<B>
+where
+    B: Read,
+{
+    /// 最後の端数を返さず、`UnexpectedEof`のエラーにする
+    /// 
+    /// エラーは[`get_err`](Self::get_err)で取れます。
+    pub fn error_on_tail(mut self) -> Self {
+        self.error_on_tail = true;
+        self
+    }
+
+    /// io操作中に生じたエラーを取得する
+    /// 
+    /// [`BufBytes::get_err`]と同じです。 [`error_on_tail`](Self::error_on_tail)の端数のエラーもここに入ります。
+    pub fn get_err(&self) -> &Option<std::io::Error> {
+        self.inner.get_err()
+    }
+}
+
+impl<B> Iterator for SwapEndian<B>
+where
+    B: Read,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos == self.len {
+            self.len = self.inner.fill_slice(&mut self.word);
+            self.pos = 0;
+            if self.len == 0 {
+                return None;
+            }
+            if self.len < self.word.len() && self.inner.error.is_none() && self.error_on_tail {
+                self.inner.error = Some(Error::new(ErrorKind::UnexpectedEof, "incomplete word at end of stream"));
+                self.len = 0;
+                return None;
+            }
+            // 端数はそのまま返す
+            if self.len == self.word.len() {
+                self.word.reverse();
+            }
+        }
+        let byte = self.word[self.pos];
+        self.pos += 1;
+        Some(byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::BufBytes;
+
+    // 2, 4, 8byteのwordで、u16/u32/u64のBEがLEになる
+    #[test]
+    fn swap_endian_test() {
+        let words: [u64; 3] = [0x0102030405060708, 0xdeadbeef, 1];
+        for size in [2, 4, 8] {
+            let be: Vec<u8> = words.iter().flat_map(|w| w.to_be_bytes()).collect();
+            let expected: Vec<u8> = be.chunks(size).flat_map(|c| c.iter().rev().copied()).collect();
+            let res: Vec<u8> = BufBytes::with_capacity(&be[..], 3).unwrap().swap_endian(size).collect();
+            assert_eq!(res, expected);
+        }
+        let be = 0x11223344u32.to_be_bytes();
+        let res: Vec<u8> = BufBytes::new(&be[..]).unwrap().swap_endian(4).collect();
+        assert_eq!(res, 0x11223344u32.to_le_bytes());
+    }
+
+    // 端数はそのまま通すか、エラーにするか選べる
+    #[test]
+    fn swap_endian_tail_test() {
+        let base = [1, 2, 3, 4, 5];
+        let res: Vec<u8> = BufBytes::new(&base[..]).unwrap().swap_endian(2).collect();
+        assert_eq!(res, [2, 1, 4, 3, 5]);
+
+        let mut iter = BufBytes::new(&base[..]).unwrap().swap_endian(2).error_on_tail();
+        assert_eq!(iter.by_ref().collect::<Vec<u8>>(), [2, 1, 4, 3]);
+        assert_eq!(iter.get_err().as_ref().unwrap().kind(), ErrorKind::UnexpectedEof);
+    }
+}
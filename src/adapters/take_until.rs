@@ -0,0 +1,77 @@
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// 条件を満たすバイトまでを返すイテレーター
+/// 
+/// [`BufBytes::take_until`]で作成します。
+pub struct TakeUntil<B, F>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    pred: F,
+    done: bool,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// predが`true`を返すバイトまでを返すイテレーターにする
+    /// 
+    /// `Iterator::take_while`と違い、predが`true`になったバイトも最後に返してから終わります。  
+    /// 残りは[`TakeUntil::into_inner`]で取り戻せるので、次の区切りを別の処理に渡せます。
+    pub fn take_until<F>(self, pred: F) -> TakeUntil<B, F>
+    where
+        F: FnMut(u8) -> bool,
+    {
+        TakeUntil { inner: self, pred, done: false }
+    }
+}
+
+impl<B, F> TakeUntil<B, F>
+where
+    B: Read,
+{
+    /// 包んでいるBufBytesを取り出す
+    /// 
+    /// 境界のバイトより後ろは、まだ消費していません。
+    pub fn into_inner(self) -> BufBytes<B> {
+        self.inner
+    }
+}
+
+impl<B, F> Iterator for TakeUntil<B, F>
+where
+    B: Read,
+    F: FnMut(u8) -> bool,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let byte = self.inner.next()?;
+        self.done = (self.pred)(byte);
+        Some(byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    // 境界のバイトまで返し、残りはinto_innerで読める
+    #[test]
+    fn take_until_test() {
+        let base = b"header;body;";
+        let mut iter = BufBytes::with_capacity(&base[..], 4).unwrap().take_until(|b| b == b';');
+        assert_eq!(iter.by_ref().collect::<Vec<u8>>(), b"header;");
+        assert_eq!(iter.next(), None);
+
+        let rest = iter.into_inner();
+        assert_eq!(rest.take_until(|b| b == b';').collect::<Vec<u8>>(), b"body;");
+    }
+}
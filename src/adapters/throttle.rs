@@ -0,0 +1,76 @@
+use std::{io::Read, thread, time::{Duration, Instant}};
+
+use crate::BufBytes;
+
+/// 1秒あたりに返すバイト数を抑えるイテレーター
+/// 
+/// [`BufBytes::throttle`]で作成します。
+#[derive(Debug)]
+pub struct Throttle<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    bytes_per_sec: u64,
+    // 最初のバイトを返した時刻と、それから返したバイト数
+    start: Option<Instant>,
+    sent: u64,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 返すペースを、1秒あたりbytes_per_secバイトまでに抑えるイテレーターにする
+    /// 
+    /// 最初のバイトからの経過時間に対して返しすぎていたら、`thread::sleep`で待ちます。  
+    /// バッファの再読み込みでまとめて読めても、返すペースは平らになります。  
+    /// スローな回線のシミュレーションや、帯域の制限に使えます。
+    /// 
+    /// # Panics
+    /// bytes_per_secが0のときはpanicします。
+    pub fn throttle(self, bytes_per_sec: u64) -> Throttle<B> {
+        assert!(bytes_per_sec != 0, "bytes_per_sec must be non-zero");
+        Throttle { inner: self, bytes_per_sec, start: None, sent: 0 }
+    }
+}
+
+impl<B> Iterator for Throttle<B>
+where
+    B: Read,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let byte = self.inner.next()?;
+        let start = *self.start.get_or_insert_with(Instant::now);
+        // sentバイト返し終わっているべき時刻まで待つ
+        let due = start + Duration::from_secs_f64(self.sent as f64 / self.bytes_per_sec as f64);
+        let now = Instant::now();
+        if due > now {
+            thread::sleep(due - now);
+        }
+        self.sent += 1;
+        Some(byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::BufBytes;
+
+    // 2000byte/sで401byte返すと、およそ200ms
+    #[test]
+    fn throttle_test() {
+        let base = vec![0; 401];
+        let start = Instant::now();
+        let count = BufBytes::new(&base[..]).unwrap().throttle(2000).count();
+        let elapsed = start.elapsed();
+
+        assert_eq!(count, 401);
+        assert!(elapsed >= Duration::from_millis(180), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(1), "{:?}", elapsed);
+    }
+}
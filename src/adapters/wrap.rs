@@ -0,0 +1,72 @@
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// widthバイトずつ区切った行を返すイテレーター
+/// 
+/// [`BufBytes::wrap`]で作成します。
+#[derive(Debug)]
+pub struct Wrap<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    width: usize,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// widthバイトずつ区切った`Vec<u8>`を返すイテレーターにする
+    /// 
+    /// 最後の行は、widthに満たない端数でも返します。
+    /// 
+    /// # Panics
+    /// widthが0のときはpanicします。
+    pub fn wrap(self, width: usize) -> Wrap<B> {
+        assert!(width != 0, "width must be non-zero");
+        Wrap { inner: self, width }
+    }
+}
+
+impl<B> Iterator for Wrap<B>
+where
+    B: Read,
+{
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = vec![0; self.width];
+        let len = self.inner.fill_slice(&mut line);
+        if len == 0 {
+            return None;
+        }
+        line.truncate(len);
+        Some(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    // 端数ありのデータで、最後の短い行も返る
+    #[test]
+    fn wrap_test() {
+        let base: Vec<u8> = (0..10).collect();
+        let bytes = BufBytes::with_capacity(&base[..], 3).unwrap();
+
+        let lines: Vec<Vec<u8>> = bytes.wrap(4).collect();
+        assert_eq!(lines, vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7], vec![8, 9]]);
+    }
+
+    // ちょうど割り切れるときは、空の行を返さない
+    #[test]
+    fn wrap_exact_test() {
+        let base: Vec<u8> = (0..8).collect();
+        let bytes = BufBytes::with_capacity(&base[..], 16).unwrap();
+
+        assert_eq!(bytes.wrap(4).count(), 2);
+    }
+}
@@ -0,0 +1,67 @@
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// 循環するキーでXORをかけて返すイテレーター
+/// 
+/// [`BufBytes::xor_with`]で作成します。
+#[derive(Debug)]
+pub struct XorDecrypt<B>
+where
+    B: Read,
+{
+    inner: BufBytes<B>,
+    key: Vec<u8>,
+    key_pos: usize,
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 各バイトに、keyを繰り返したものでXORをかけるイテレーターにする
+    /// 
+    /// キーの位置は返したバイト数に合わせて進むので、バッファの再読み込みをまたいでもずれません。
+    /// 
+    /// # Panics
+    /// keyが空のときはpanicします。
+    pub fn xor_with(self, key: Vec<u8>) -> XorDecrypt<B> {
+        assert!(!key.is_empty(), "key must not be empty");
+        XorDecrypt { inner: self, key, key_pos: 0 }
+    }
+}
+
+impl<B> Iterator for XorDecrypt<B>
+where
+    B: Read,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let byte = self.inner.next()?;
+        let res = byte ^ self.key[self.key_pos];
+        self.key_pos = (self.key_pos + 1) % self.key.len();
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    // 既知の暗号文を、バッファ境界をまたいで復号する
+    #[test]
+    fn xor_with_test() {
+        let plain = b"attack at dawn!";
+        let key = b"key";
+        let cipher: Vec<u8> = plain.iter()
+            .zip(key.iter().cycle())
+            .map(|(p, k)| p ^ k)
+            .collect();
+        assert_eq!(&cipher[..4], &[0x0a, 0x11, 0x0d, 0x0a]);
+
+        // キー長(3)とずれた4byteバッファで読む
+        let bytes = BufBytes::with_capacity(&cipher[..], 4).unwrap();
+        assert_eq!(bytes.xor_with(key.to_vec()).collect::<Vec<u8>>(), plain);
+    }
+}
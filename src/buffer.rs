@@ -0,0 +1,276 @@
+//! BufBytesの内部バッファ
+//! 
+//! 普段は`Vec<u8>`をそのまま使い、[`with_aligned_capacity`](crate::BufBytes::with_aligned_capacity)のときだけ  
+//! `std::alloc`でアラインして確保した領域を使います。  
+//! どちらも`[u8]`としてderefできるので、BufBytesの側からは区別しません。  
+//! [`from_pool`](crate::BufBytes::from_pool)のときは、スレッドローカルのプールから借りた`Vec<u8>`を使い、  
+//! 捨てるときにプールへ返します。  
+//! `zeroize` featureでは、捨てるときと、確保し直して古い領域を手放すときに、その領域全体を0で上書きします。
+
+use std::{alloc::{self, Layout}, cell::RefCell, fmt, io::{Error, ErrorKind, Result}, ops::{Deref, DerefMut}, ptr::NonNull};
+
+// プールから借りるバッファの大きさと、プールに置いておく数の上限
+const POOL_BUF_SIZE: usize = 8192;
+const POOL_MAX: usize = 16;
+
+thread_local! {
+    static POOL: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+}
+
+pub(crate) enum Buffer {
+    Vec(Vec<u8>),
+    Aligned(AlignedBuf),
+    // Dropでプールに返すVec
+    Pooled(Vec<u8>),
+}
+
+/// alignの倍数のアドレスから始まるバッファ
+/// 
+/// `Vec`と同じく、`len`までが初期化済みで、`cap`バイトを確保しています。  
+/// (`cap`が0のときは何も確保せず、ptrはalignの値をアドレスにしたダングリングポインタ)
+pub(crate) struct AlignedBuf {
+    ptr: NonNull<u8>,
+    len: usize,
+    cap: usize,
+    align: usize,
+}
+
+impl Buffer {
+    /// スレッドローカルのプールから、POOL_BUF_SIZEバイトの0埋めされたバッファを借りる
+    /// 
+    /// プールが空なら新しく確保する
+    pub(crate) fn pooled() -> Self {
+        let mut vec = POOL.with(|pool| pool.borrow_mut().pop()).unwrap_or_default();
+        vec.clear();
+        vec.resize(POOL_BUF_SIZE, 0);
+        Self::Pooled(vec)
+    }
+
+    /// alignの倍数のアドレスから始まる、sizeバイトの0埋めされたバッファを確保する
+    /// 
+    /// alignが2のべき乗でなければ`InvalidInput`を、確保できなければ`OutOfMemory`を返す
+    pub(crate) fn aligned(size: usize, align: usize) -> Result<Self> {
+        Layout::from_size_align(size, align).map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        let mut buf = AlignedBuf {
+            ptr: AlignedBuf::dangling(align),
+            len: 0,
+            cap: 0,
+            align,
+        };
+        buf.realloc(size)?;
+        // 確保したばかりの領域は未初期化なので、0で埋めてから使う
+        unsafe { buf.ptr.as_ptr().write_bytes(0, size) };
+        buf.len = size;
+        Ok(Self::Aligned(buf))
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const u8 {
+        match self {
+            Self::Vec(vec) | Self::Pooled(vec) => vec.as_ptr(),
+            Self::Aligned(buf) => buf.ptr.as_ptr(),
+        }
+    }
+
+    /// 先頭のポインタ
+    /// 
+    /// `Vec::as_mut_ptr`と同じく、`&mut [u8]`を作らずに取り出す
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut u8 {
+        match self {
+            Self::Vec(vec) | Self::Pooled(vec) => vec.as_mut_ptr(),
+            Self::Aligned(buf) => buf.ptr.as_ptr(),
+        }
+    }
+
+    // テストで、確保した大きさを調べるのに使う
+    #[cfg(any(test, feature = "zeroize"))]
+    pub(crate) fn capacity(&self) -> usize {
+        match self {
+            Self::Vec(vec) | Self::Pooled(vec) => vec.capacity(),
+            Self::Aligned(buf) => buf.cap,
+        }
+    }
+
+    /// `Vec::resize`と同じ。 アラインしたバッファは、アラインを保ったまま確保し直す
+    pub(crate) fn resize(&mut self, new_len: usize, value: u8) {
+        #[cfg(feature = "zeroize")]
+        if new_len > self.capacity() {
+            self.realloc_wiped(new_len);
+        }
+        match self {
+            Self::Vec(vec) | Self::Pooled(vec) => vec.resize(new_len, value),
+            Self::Aligned(buf) => {
+                if new_len > buf.cap {
+                    buf.realloc(new_len).unwrap_or_else(|_| {
+                        alloc::handle_alloc_error(Layout::from_size_align(new_len, buf.align).unwrap())
+                    });
+                }
+                if new_len > buf.len {
+                    unsafe { buf.ptr.as_ptr().add(buf.len).write_bytes(value, new_len - buf.len) };
+                }
+                buf.len = new_len;
+            },
+        }
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        match self {
+            Self::Vec(vec) | Self::Pooled(vec) => vec.truncate(len),
+            Self::Aligned(buf) => buf.len = buf.len.min(len),
+        }
+    }
+
+    pub(crate) fn shrink_to_fit(&mut self) {
+        #[cfg(feature = "zeroize")]
+        if self.len() < self.capacity() {
+            self.realloc_wiped(self.len());
+            return;
+        }
+        match self {
+            Self::Vec(vec) | Self::Pooled(vec) => vec.shrink_to_fit(),
+            Self::Aligned(buf) => {
+                // 縮めるだけなので失敗しても困らない。 そのときは大きいまま使う
+                let _ = buf.realloc(buf.len);
+            },
+        }
+    }
+}
+
+impl AlignedBuf {
+    fn dangling(align: usize) -> NonNull<u8> {
+        NonNull::new(std::ptr::without_provenance_mut(align)).unwrap()
+    }
+
+    /// 確保した領域をcapバイトに変える。 len以下にはしない
+    fn realloc(&mut self, cap: usize) -> Result<()> {
+        let cap = cap.max(self.len);
+        if cap == self.cap {
+            return Ok(());
+        }
+        let new_layout = Layout::from_size_align(cap, self.align).map_err(|e| Error::new(ErrorKind::OutOfMemory, e))?;
+        let ptr = unsafe {
+            if cap == 0 {
+                alloc::dealloc(self.ptr.as_ptr(), Layout::from_size_align_unchecked(self.cap, self.align));
+                Self::dangling(self.align).as_ptr()
+            } else if self.cap == 0 {
+                alloc::alloc(new_layout)
+            } else {
+                // reallocは元のレイアウトのアラインを保つ
+                alloc::realloc(self.ptr.as_ptr(), Layout::from_size_align_unchecked(self.cap, self.align), cap)
+            }
+        };
+        self.ptr = NonNull::new(ptr).ok_or_else(|| Error::new(ErrorKind::OutOfMemory, "failed to allocate aligned buffer"))?;
+        self.cap = cap;
+        Ok(())
+    }
+}
+
+// zeroize featureでは、Dropで中身を消してから解放する
+// (resizeやshrink_to_fitで確保し直すときの古い領域は、realloc_wipedで消す)
+// プールから借りたVecは、消してからプールに返す
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        #[cfg(feature = "zeroize")]
+        self.wipe();
+        if let Self::Pooled(vec) = self {
+            let vec = std::mem::take(vec);
+            // スレッドの終了中でプールが使えなければ、そのまま解放する
+            let _ = POOL.try_with(|pool| {
+                let mut pool = pool.borrow_mut();
+                if pool.len() < POOL_MAX {
+                    pool.push(vec);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Buffer {
+    /// 確保した領域全体(len以降の使っていない部分も)を0で上書きする
+    pub(crate) fn wipe(&mut self) {
+        use zeroize::Zeroize;
+        match self {
+            Self::Vec(vec) | Self::Pooled(vec) => {
+                vec.zeroize();
+            },
+            Self::Aligned(buf) => {
+                // cap以下は、resizeで一度は初期化している
+                unsafe { std::slice::from_raw_parts_mut(buf.ptr.as_ptr(), buf.cap) }.zeroize();
+            },
+        }
+    }
+
+    /// capバイトの新しい領域に中身を移し、古い領域は0で上書きしてから解放する
+    /// 
+    /// `Vec`や`realloc`に任せると、古い領域を消せないままアロケータに返してしまうので、自分で移す
+    fn realloc_wiped(&mut self, cap: usize) {
+        match self {
+            Self::Vec(vec) | Self::Pooled(vec) => {
+                let mut moved = Vec::with_capacity(cap);
+                moved.extend_from_slice(vec);
+                // 古い方は、プールに返さずにDropで消して解放する
+                drop(Self::Vec(std::mem::replace(vec, moved)));
+            },
+            Self::Aligned(buf) => {
+                let mut moved = AlignedBuf {
+                    ptr: AlignedBuf::dangling(buf.align),
+                    len: 0,
+                    cap: 0,
+                    align: buf.align,
+                };
+                moved.realloc(cap).unwrap_or_else(|_| {
+                    alloc::handle_alloc_error(Layout::from_size_align(cap, buf.align).unwrap())
+                });
+                unsafe { buf.ptr.as_ptr().copy_to_nonoverlapping(moved.ptr.as_ptr(), buf.len) };
+                moved.len = buf.len;
+                drop(Self::Aligned(std::mem::replace(buf, moved)));
+            },
+        }
+    }
+}
+
+impl Drop for AlignedBuf {
+    fn drop(&mut self) {
+        if self.cap > 0 {
+            unsafe { alloc::dealloc(self.ptr.as_ptr(), Layout::from_size_align_unchecked(self.cap, self.align)) };
+        }
+    }
+}
+
+impl Deref for Buffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Vec(vec) | Self::Pooled(vec) => vec,
+            Self::Aligned(buf) => unsafe { std::slice::from_raw_parts(buf.ptr.as_ptr(), buf.len) },
+        }
+    }
+}
+
+impl DerefMut for Buffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        match self {
+            Self::Vec(vec) | Self::Pooled(vec) => vec,
+            Self::Aligned(buf) => unsafe { std::slice::from_raw_parts_mut(buf.ptr.as_ptr(), buf.len) },
+        }
+    }
+}
+
+impl Default for Buffer {
+    fn default() -> Self {
+        Self::Vec(Vec::new())
+    }
+}
+
+impl From<Vec<u8>> for Buffer {
+    fn from(vec: Vec<u8>) -> Self {
+        Self::Vec(vec)
+    }
+}
+
+impl fmt::Debug for Buffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
@@ -0,0 +1,170 @@
+//! 別スレッドで読み込んで、チャネルへ流すメソッドたち
+
+use std::{io::Read, sync::mpsc::{self, Receiver, Sender, SyncSender}, thread::{self, JoinHandle}};
+
+use crate::BufBytes;
+
+impl<B> BufBytes<B>
+where
+    B: Read + Send + 'static,
+{
+    /// バックグラウンドスレッドで読み込み、chunkバイトずつチャネルへ送る
+    /// 
+    /// 最後のチャンクは、chunkに満たない端数でも送ります。  
+    /// EOFかio処理のエラーになるか、`Receiver`が捨てられたら、チャネルを閉じてスレッドを終えます。
+    /// 
+    /// # Panics
+    /// chunkが0のときはpanicします。
+    pub fn into_channel(self, chunk: usize) -> (JoinHandle<()>, Receiver<Vec<u8>>) {
+        let (tx, rx) = mpsc::channel();
+        (spawn_sending(self.wrap(chunk), move |chunk| tx.send(chunk).is_ok()), rx)
+    }
+
+    /// [`into_channel`](Self::into_channel)の、有界チャネルを使う版
+    /// 
+    /// チャネルに溜まったチャンクがboundに達すると、受信されるまで読み込みスレッドがブロックします。  
+    /// 受信側が遅くても、読み込んだデータがメモリに溜まり続けません。
+    /// 
+    /// # Panics
+    /// chunkが0のときはpanicします。
+    pub fn into_channel_bounded(self, chunk: usize, bound: usize) -> (JoinHandle<()>, Receiver<Vec<u8>>) {
+        let (tx, rx) = mpsc::sync_channel(bound);
+        (spawn_sending(self.wrap(chunk), move |chunk| tx.send(chunk).is_ok()), rx)
+    }
+
+    /// バックグラウンドスレッドで読み込み、各バイトをn個の`Receiver`へ複製して送る
+    /// 
+    /// 1回の読み込みで、ハッシュの計算とパースのような独立した処理をまとめて動かせます。  
+    /// 捨てられた`Receiver`には送るのをやめ、全部捨てられたらスレッドを終えます。  
+    /// EOFかio処理のエラーになったら、全部のチャネルを閉じます。  
+    /// (エラーの中身は受け取れないので、必要なら[`try_block`](Self::try_block)などで読んでください)
+    pub fn broadcast(self, n: usize) -> Vec<Receiver<u8>> {
+        let (txs, rxs): (Vec<Sender<u8>>, Vec<Receiver<u8>>) = (0..n).map(|_| mpsc::channel()).unzip();
+        self.broadcast_to(txs, |tx, byte| tx.send(byte).is_ok());
+        rxs
+    }
+
+    /// [`broadcast`](Self::broadcast)の、有界チャネルを使う版
+    /// 
+    /// どれかのチャネルに溜まったバイトがboundに達すると、受信されるまで読み込みスレッドがブロックします。  
+    /// いちばん遅い`Receiver`に合わせて読み込むので、メモリが溜まり続けません。
+    pub fn broadcast_bounded(self, n: usize, bound: usize) -> Vec<Receiver<u8>> {
+        let (txs, rxs): (Vec<SyncSender<u8>>, Vec<Receiver<u8>>) = (0..n).map(|_| mpsc::sync_channel(bound)).unzip();
+        self.broadcast_to(txs, |tx, byte| tx.send(byte).is_ok());
+        rxs
+    }
+
+    /// バックグラウンドスレッドで読み込み、各バイトをsendでtxsへ送る
+    /// 
+    /// sendがfalseを返した(`Receiver`が捨てられた)txは外し、全部外れたらスレッドを終える
+    fn broadcast_to<S: Send + 'static>(self, mut txs: Vec<S>, send: fn(&S, u8) -> bool) {
+        spawn_sending(self, move |byte| {
+            txs.retain(|tx| send(tx, byte));
+            !txs.is_empty()
+        });
+    }
+}
+
+/// バックグラウンドスレッドで、itemsを順にsendへ渡す
+/// 
+/// itemsが終わるか、sendがfalseを返したら(受信側がいなくなったら)スレッドを終える。  
+/// `Sender`と`SyncSender`のどちらで送るかは、sendで決める
+fn spawn_sending<I>(items: I, mut send: impl FnMut(I::Item) -> bool + Send + 'static) -> JoinHandle<()>
+where
+    I: Iterator + Send + 'static,
+{
+    thread::spawn(move || {
+        for item in items {
+            if !send(item) {
+                break;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    // 送られたバイトを連結すると、元のデータになる
+    #[test]
+    fn into_channel_test() {
+        let base: Vec<u8> = (0..=255).cycle().take(10000).collect();
+        let bytes = BufBytes::with_capacity(std::io::Cursor::new(base.clone()), 64).unwrap();
+
+        let (handle, rx) = bytes.into_channel(100);
+        let mut res = Vec::new();
+        for chunk in rx {
+            assert!(chunk.len() <= 100);
+            res.extend(chunk);
+        }
+        handle.join().unwrap();
+        assert_eq!(res, base);
+    }
+
+    // 全部のReceiverが同じバイト列を受け取る
+    #[test]
+    fn broadcast_test() {
+        let base: Vec<u8> = (0..=255).cycle().take(3000).collect();
+        let bytes = BufBytes::with_capacity(std::io::Cursor::new(base.clone()), 64).unwrap();
+
+        let mut rxs = bytes.broadcast(3);
+        // 1つ捨てても、残りには届く
+        rxs.pop();
+        let handles: Vec<_> = rxs.into_iter()
+            .map(|rx| std::thread::spawn(move || rx.iter().collect::<Vec<u8>>()))
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), base);
+        }
+    }
+
+    // 受信しない間は読み込みが止まり、読み込んだ量が上限を超えない
+    #[test]
+    fn into_channel_bounded_test() {
+        let bytes = BufBytes::with_capacity(std::io::repeat(7), 64).unwrap();
+        let progress = bytes.progress_handle();
+
+        let (handle, rx) = bytes.into_channel_bounded(100, 2);
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        // バッファ + チャネルの2つ + 送信待ちと作りかけの2つ
+        assert!(progress.bytes_read() <= 64 + 4 * 100, "{}", progress.bytes_read());
+        // 受信すれば、また読み進める
+        assert_eq!(rx.iter().take(10).flatten().count(), 1000);
+        drop(rx);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn broadcast_bounded_test() {
+        let bytes = BufBytes::with_capacity(std::io::repeat(7), 64).unwrap();
+        let progress = bytes.progress_handle();
+
+        let rxs = bytes.broadcast_bounded(2, 16);
+        // 片方だけ受信しても、もう片方が受信しないので止まる
+        assert_eq!(rxs[0].iter().take(16).count(), 16);
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert!(progress.bytes_read() <= 64 * 2, "{}", progress.bytes_read());
+        // 両方が受信すれば、また読み進める
+        let handles: Vec<_> = rxs.into_iter()
+            .map(|rx| std::thread::spawn(move || rx.iter().take(1000).all(|b| b == 7)))
+            .collect();
+        assert!(handles.into_iter().all(|handle| handle.join().unwrap()));
+    }
+
+    // エラーが起きたら、そこまでを送ってチャネルを閉じる
+    #[test]
+    fn broadcast_error_test() {
+        struct Broken;
+        impl std::io::Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("broken"))
+            }
+        }
+        let base = std::io::Read::chain(&b"abc"[..], Broken);
+        let bytes = BufBytes::with_capacity(base, 2).unwrap();
+        for rx in bytes.broadcast(2) {
+            assert_eq!(rx.iter().collect::<Vec<u8>>(), b"abc");
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! 消費したバイトから検算用の値を求めるメソッドたち
+
+use std::io::Read;
+
+use crate::BufBytes;
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// これまでに消費した全バイトのパリティ
+    /// 
+    /// 全ビットのXORで、1のビットが奇数個なら`true`です。  
+    /// `next()`や[`consume`](Self::consume)で消費するたびに更新し、  
+    /// [`peek`](Self::peek)などで覗いただけのバイトは含みません。  
+    /// [`unread`](Self::unread)で押し戻したバイトは、もう一度消費するまで含みません。  
+    /// 前回呼んだときから消費したバイトだけを畳み込むので、1バイトごとに呼んでも全体で線形時間です。
+    pub fn parity(&self) -> bool {
+        self.checksum_xor().count_ones() % 2 == 1
+    }
+
+    /// これまでに消費した全バイトの和(mod 256)
+    /// 
+    /// [`parity`](Self::parity)と同じく、消費したバイトだけを数え、前回から消費した分だけを足します。
+    pub fn checksum_add(&self) -> u8 {
+        self.fold_consumed();
+        self.sum_acc.get()
+    }
+
+    /// これまでに消費した全バイトのXOR
+    /// 
+    /// シリアル通信のBCCなどに使えます。 [`parity`](Self::parity)と同じく、消費したバイトだけを数え、前回から消費した分だけを畳み込みます。
+    pub fn checksum_xor(&self) -> u8 {
+        self.fold_consumed();
+        self.xor_acc.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    #[test]
+    fn parity_test() {
+        // 0b0000_0111(3bit), 0b1000_0001(2bit), 0b0001_0000(1bit)
+        let base = [0x07, 0x81, 0x10];
+        let mut bytes = BufBytes::with_capacity(&base[..], 2).unwrap();
+        assert!(!bytes.parity());
+
+        assert_eq!(bytes.next(), Some(0x07));
+        assert!(bytes.parity());
+        // 覗いただけでは変わらない
+        bytes.peek_n(2).unwrap();
+        assert!(bytes.parity());
+        bytes.consume(1);
+        assert!(bytes.parity());
+        bytes.next();
+        assert!(!bytes.parity());
+
+        // 押し戻すと、消費していないことになる
+        bytes.unread_byte(0x01).unwrap();
+        assert!(bytes.parity());
+    }
+
+    // 1バイトごとに呼んでも、再読み込みや押し戻しをまたいで同じ値になる
+    #[test]
+    fn parity_each_byte_test() {
+        let base: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let mut bytes = BufBytes::with_capacity(&base[..], 7).unwrap();
+        let mut xor = 0;
+        while let Some(b) = bytes.next() {
+            xor ^= b;
+            assert_eq!(bytes.parity(), xor.count_ones() % 2 == 1);
+            if b == 100 {
+                bytes.unread_byte(b).unwrap();
+                xor ^= b;
+                assert_eq!(bytes.parity(), xor.count_ones() % 2 == 1);
+                assert_eq!(bytes.next(), Some(b));
+                xor ^= b;
+            }
+        }
+        assert_eq!(bytes.checksum_xor(), xor);
+    }
+
+    // 1バイトごとに呼んだり、バッファを詰め直したりしても、同じバイトを2度足さない
+    #[test]
+    fn checksum_each_byte_test() {
+        let base: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let mut bytes = BufBytes::with_capacity(&base[..], 16).unwrap();
+        let mut sum = 0u8;
+        while let Some(b) = bytes.next() {
+            sum = sum.wrapping_add(b);
+            assert_eq!(bytes.checksum_add(), sum);
+            // 先を覗くと、未消費のバイトを先頭に寄せてから読み足す
+            if b % 7 == 0 {
+                let _ = bytes.fill_buf_at_least(10);
+                assert_eq!(bytes.checksum_add(), sum);
+            }
+        }
+        assert_eq!(bytes.checksum_add(), base.iter().fold(0, |acc: u8, &b| acc.wrapping_add(b)));
+    }
+
+    #[test]
+    fn checksum_test() {
+        // STX 'A' 'B' ETXのBCCとSUM
+        let base = [0x02, 0x41, 0x42, 0x03, 0xff];
+        let mut bytes = BufBytes::with_capacity(&base[..], 3).unwrap();
+        bytes.by_ref().take(4).for_each(drop);
+        assert_eq!(bytes.checksum_xor(), 0x02 ^ 0x41 ^ 0x42 ^ 0x03);
+        assert_eq!(bytes.checksum_add(), 0x88);
+        // 覗いただけでは変わらない
+        bytes.peek().unwrap();
+        assert_eq!(bytes.checksum_add(), 0x88);
+        // 256を超えたら巻き戻る
+        bytes.next();
+        assert_eq!(bytes.checksum_add(), 0x87);
+        assert_eq!(bytes.checksum_xor(), 0x02 ^ 0x41 ^ 0x42 ^ 0x03 ^ 0xff);
+
+        bytes.unread_byte(0xff).unwrap();
+        assert_eq!(bytes.checksum_add(), 0x88);
+    }
+}
@@ -0,0 +1,54 @@
+//! `File`専用のメソッドたち
+
+use std::{fs::File, io::Result};
+
+use crate::BufBytes;
+
+impl BufBytes<File> {
+    /// ファイルシステムのブロックサイズの倍数のバッファで、BufBytesを作成
+    /// 
+    /// `metadata`からブロックサイズ(unixの`st_blksize`)を調べ、  
+    /// 8192以上になる最小の倍数をバッファーサイズにします。  
+    /// ブロックサイズが取れない環境では8192になります。
+    pub fn new_blocksize_aligned(file: File) -> Result<Self> {
+        let size = match Self::block_size(&file)? {
+            0 => 8192,
+            block => 8192usize.div_ceil(block) * block,
+        };
+        Self::with_capacity(file, size)
+    }
+
+    /// ファイルシステムのブロックサイズ。 わからなければ0
+    #[cfg(unix)]
+    fn block_size(file: &File) -> Result<usize> {
+        use std::os::unix::fs::MetadataExt;
+        Ok(file.metadata()?.blksize() as usize)
+    }
+
+    #[cfg(not(unix))]
+    fn block_size(_file: &File) -> Result<usize> {
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Seek, SeekFrom, Write};
+
+    use crate::BufBytes;
+
+    #[test]
+    fn new_blocksize_aligned_test() {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&[7; 10000]).unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+
+        let block = BufBytes::block_size(&file).unwrap();
+        let bytes = BufBytes::new_blocksize_aligned(file).unwrap();
+        assert!(bytes.capacity() >= 8192);
+        if block > 0 {
+            assert_eq!(bytes.capacity() % block, 0);
+        }
+        assert_eq!(bytes.count(), 10000);
+    }
+}
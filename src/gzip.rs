@@ -0,0 +1,66 @@
+//! 読み込みながらgzipに圧縮するメソッドたち(`flate2` feature)
+
+use std::io::{Read, Result, Write};
+
+use flate2::{Compression, write::GzEncoder};
+
+use crate::BufBytes;
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 残りのバイトを、gzipに圧縮しながらoutへ書き出す
+    /// 
+    /// バッファごとに`GzEncoder`へ書き込むので、全体をメモリに載せずに圧縮できます。  
+    /// 最後まで書けたら、圧縮前のバイト数を返します。  
+    /// io処理でエラーが起きたときや、outへの書き込みに失敗したときはそのエラーを返します。
+    pub fn gzip_to<W: Write>(&mut self, out: W) -> Result<u64> {
+        let mut encoder = GzEncoder::new(out, Compression::default());
+        let mut written = 0;
+        self.pump(|chunk| {
+            encoder.write_all(chunk)?;
+            written += chunk.len() as u64;
+            Ok(())
+        })?;
+        encoder.finish()?;
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use crate::BufBytes;
+
+    // 伸張すると元に戻る
+    #[test]
+    fn gzip_to_test() {
+        let base: Vec<u8> = (0..20000u32).map(|i| (i % 97) as u8).collect();
+        let mut bytes = BufBytes::with_capacity(&base[..], 1000).unwrap();
+        let mut gz = Vec::new();
+        assert_eq!(bytes.gzip_to(&mut gz).unwrap(), 20000);
+        assert!(gz.len() < base.len());
+
+        let mut res = Vec::new();
+        GzDecoder::new(&gz[..]).read_to_end(&mut res).unwrap();
+        assert_eq!(res, base);
+    }
+
+    // 途中まで読んだら、残りだけを圧縮する
+    #[test]
+    fn gzip_to_rest_test() {
+        let base = b"header\nbody body body";
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+        bytes.by_ref().take(7).for_each(drop);
+        let mut gz = Vec::new();
+        assert_eq!(bytes.gzip_to(&mut gz).unwrap(), 14);
+
+        let mut res = Vec::new();
+        GzDecoder::new(&gz[..]).read_to_end(&mut res).unwrap();
+        assert_eq!(res, b"body body body");
+    }
+}
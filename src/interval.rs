@@ -0,0 +1,87 @@
+//! 一定バイト数ごとに通知するメソッドたち
+
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// on_intervalで登録したコールバックと、次に呼ぶオフセット
+pub(crate) struct IntervalHook {
+    interval: u64,
+    next: u64,
+    f: Box<dyn FnMut(u64) + Send>,
+}
+
+impl IntervalHook {
+    /// offsetまで消費したときに呼ぶ。 越えたintervalの倍数ごとにfを呼ぶ
+    pub(crate) fn reached(&mut self, offset: u64) {
+        while self.next <= offset {
+            (self.f)(self.next);
+            self.next += self.interval;
+        }
+    }
+}
+
+impl std::fmt::Debug for IntervalHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IntervalHook").field("interval", &self.interval).field("next", &self.next).finish_non_exhaustive()
+    }
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 読み取り位置がintervalの倍数に達するたびに、そのオフセットでfを呼ぶ
+    /// 
+    /// 一定量ごとのバッチコミットや、チェックポイントの作成に使えます。  
+    /// `next()`や[`consume`](Self::consume)で消費したときに調べます。  
+    /// [`consume`](Self::consume)でまとめて越えたときは、越えた倍数ごとに順に呼びます。  
+    /// [`unread`](Self::unread)などで戻して読み直しても、同じオフセットでもう一度は呼びません。  
+    /// もう一度呼ぶと、前のfと置き換えます。
+    /// 
+    /// # Panics
+    /// intervalが0のときはpanicします。
+    pub fn on_interval<F: FnMut(u64) + Send + 'static>(&mut self, interval: u64, f: F) {
+        assert!(interval != 0, "interval must be non-zero");
+        let next = (self.last_good_position() / interval + 1) * interval;
+        self.tracking_mut().interval = Some(IntervalHook { interval, next, f: Box::new(f) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::BufBytes;
+
+    // 1000byteごとに呼ばれる
+    #[test]
+    fn on_interval_test() {
+        let base = vec![0u8; 3500];
+        let mut bytes = BufBytes::with_capacity(&base[..], 64).unwrap();
+        let offsets = Arc::new(Mutex::new(vec![]));
+        let sink = Arc::clone(&offsets);
+        bytes.on_interval(1000, move |offset| sink.lock().unwrap().push(offset));
+
+        bytes.by_ref().take(999).for_each(drop);
+        assert!(offsets.lock().unwrap().is_empty());
+        bytes.next();
+        assert_eq!(*offsets.lock().unwrap(), [1000]);
+        bytes.by_ref().for_each(drop);
+        assert_eq!(*offsets.lock().unwrap(), [1000, 2000, 3000]);
+    }
+
+    // consumeでまとめて越えても、倍数ごとに呼ばれる
+    #[test]
+    fn on_interval_consume_test() {
+        let base = [0u8; 100];
+        let mut bytes = BufBytes::with_capacity(&base[..], 64).unwrap();
+        bytes.by_ref().take(5).for_each(drop);
+        let offsets = Arc::new(Mutex::new(vec![]));
+        let sink = Arc::clone(&offsets);
+        bytes.on_interval(10, move |offset| sink.lock().unwrap().push(offset));
+
+        bytes.consume(30);
+        assert_eq!(*offsets.lock().unwrap(), [10, 20, 30]);
+    }
+}
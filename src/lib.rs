@@ -3,78 +3,838 @@
 //! unsafeを使ってるので、あんまり保証がないのが特徴です。  
 //! ポインタ勉強用...  
 
-use std::{io::{Error, Read, Result}, ptr::NonNull};
+use std::{cell::Cell, collections::VecDeque, io::{Error, Read, Result}, ptr::NonNull, sync::{Arc, atomic::{AtomicBool, AtomicU64, Ordering}}};
 
+pub mod adapters;
+mod buffer;
+mod channel;
+mod checksum;
+mod file;
+#[cfg(feature = "flate2")]
+mod gzip;
+mod interval;
+#[cfg(feature = "prometheus")]
+mod metrics;
+mod peek;
+mod profile;
+mod progress;
+mod read;
+mod scan;
+mod seek;
+mod snapshot;
+mod stats;
+mod tracking;
+#[cfg(feature = "sha2")]
+mod verify;
+
+// with_adaptiveで使うバッファーサイズの下限と上限
+const ADAPTIVE_MIN: usize = 1024;
+const ADAPTIVE_MAX: usize = 1 << 20;
+// with_adaptiveで、この回数続けて短い読み込みが来たらバッファを縮める
+const ADAPTIVE_SHRINK_AFTER: u32 = 4;
+
+use buffer::Buffer;
+
+pub use profile::LatencyStats;
+pub use progress::ProgressHandle;
+pub use seek::Bookmark;
+pub use snapshot::Snapshot;
+
+/// バッファリングしながら、バイトごとにデータを取り出すイテレーター
+/// 
+/// # 安全性
+/// `buf_ptr`/`buf_ptr_end`は`buf`の中をさす生ポインタなので、自己参照しているように見えます。  
+/// ただし、さしているのは`Vec`が確保したヒープ領域で、`BufBytes`自体をmoveしても動きません。  
+/// そのため`Pin`で固定する必要はありません。  
+/// 
+/// 代わりに、次のことを守っています。
+/// - `buf`を再確保したり書き込んだりした後は、必ず`set_buf_ptr`でポインタを張り直す
+/// - ポインタは`buf.as_mut_ptr()`から作り、`&mut buf[..]`を経由しない(Miriのstacked borrowsで無効にならないように)
+/// - 未消費のバイトは`buf_ptr`から`buf_ptr_end`まで(両端を含む)で、空のときは`buf_ptr > buf_ptr_end`
+/// 
+/// これらはMiriでテストしています。(`cargo +nightly miri test`)
 #[derive(Debug)]
 pub struct BufBytes<B>
 where
     B: Read,
 {
     base: B,
-    buf: Vec<u8>,
+    buf: Buffer,
     buf_ptr: NonNull<u8>,
     buf_ptr_end: NonNull<u8>,
+    // next()で、記録せずにそのまま返してよい最後のバイト
+    // (trackingがあるときは、どのbuf_ptrよりも前をさして、毎回track_consumedを通す)
+    fast_end: NonNull<u8>,
+    // 消費したが、まだxor_accなどに畳み込んでいないバイトの先頭(buf_ptrの手前まで)
+    // (&selfのparityなどからも畳み込んで進めるので、Cellに入れる)
+    tracked: Cell<NonNull<u8>>,
     error: Option<std::io::Error>,
+    // with_eager_errorで使う先読み用のバッファと、読み込めたデータ長
+    ahead: Option<Buffer>,
+    ahead_len: usize,
+    // baseから読み込んだ総バイト数
+    read_total: u64,
+    // 別スレッドから覗ける、baseから読み込んだ総バイト数
+    progress: Arc<AtomicU64>,
+    // baseがSeekできるときに、最後に調べたbaseのサイズ
+    stream_len: Option<u64>,
+    // with_cancelで使う、読み込みを止めるためのトークン
+    cancel: Option<Arc<AtomicBool>>,
+    // with_adaptiveで使う。 Someなら適応モードで、続けて短い読み込みが来た回数
+    adaptive: Option<u32>,
+    // with_retryで使う、readのエラーを再試行する回数と、再試行するエラーの種類(Noneなら全部)
+    retry: usize,
+    retry_kinds: Option<Vec<std::io::ErrorKind>>,
+    // with_fallbackで使う、baseがEOFかエラーになったときに切り替えるReader
+    fallback: Option<B>,
+    // with_rewind_cache/with_ring/with_recording/on_intervalで使う、消費したバイトの記録
+    tracking: Option<Box<tracking::Tracking>>,
+    // register_metricsで登録したメトリクス
+    #[cfg(feature = "prometheus")]
+    metrics: Option<metrics::Metrics>,
+    // 最後の再読み込みで、バッファが埋まった割合(fill_ratioで使う)
+    fill_ratio: f64,
+    // 最後にsync_consumedしたときまでに消費できたバイトの末尾のオフセット(last_good_positionで使う)
+    last_good: u64,
+    // trackedより前に消費したバイトを全部XORしたもの(parity, checksum_xorで使う)
+    xor_acc: Cell<u8>,
+    // trackedより前に消費したバイトを全部wrappingで足したもの(checksum_addで使う)
+    sum_acc: Cell<u8>,
+    // with_profilingのときの、baseのreadごとの所要時間
+    latencies: Option<Vec<std::time::Duration>>,
+    // 最後にpeek/peek_nで覗いた範囲の終わりの読み取り位置(discard_peekedで使う)
+    peek_end: u64,
 }
 
+// buf_ptr/buf_ptr_endは自分が持っているbufの中をさすだけなので、
+// BufBytesごと別スレッドへ送っても問題ない(Vecを送るのと同じ)
+unsafe impl<B> Send for BufBytes<B>
+where
+    B: Read + Send,
+{}
+
 impl<B> BufBytes<B>
 where
     B: Read,
 {
     /// BufBytesを作成
     /// 
-    /// バッファーサイズは8192になります。
+    /// バッファーサイズは8192になります。  
+    /// 空のファイルは`ErrorKind::UnexpectedEof`のエラーになります。
     pub fn new(base: B) -> Result<Self> {
         Self::with_capacity(base, 8192)
     }
 
     /// BufBytesを作成
     /// 
-    /// バッファーサイズがいじれます。
-    pub fn with_capacity(mut base: B, size: usize) -> Result<Self> {
-        // バッファ作成し、base(ファイルなど)からデータを読み込む
-        let mut buf = vec![0; size];
-        // buf_lenは読み込めたデータ長=バッファのサイズ
-        let buf_len = base.read(buf.as_mut())?;
+    /// バッファーサイズがいじれます。  
+    /// baseの`read`は1回ずつしか呼ばないので、対話的な入力のように少しずつ返すReaderでも、  
+    /// バッファが埋まるのを待たずに、読めたバイトからすぐに返します。
+    pub fn with_capacity(base: B, size: usize) -> Result<Self> {
+        Self::unfilled(base, vec![0; size]).first_fill()
+    }
 
-        if buf_len == 0 {
-            return Err(Error::other("0 size file"));
+    /// バッファを確保できなくてもpanicしないBufBytesを作成
+    /// 
+    /// [`with_capacity`](Self::with_capacity)は、大きすぎるsizeを渡すとpanicします。  
+    /// こちらはバッファを確保できなければ`OutOfMemory`エラーを返すので、  
+    /// 外から来たsizeをそのまま使っても安全です。
+    pub fn try_with_capacity(base: B, size: usize) -> Result<Self> {
+        let mut buf = Vec::new();
+        buf.try_reserve_exact(size).map_err(|e| Error::new(std::io::ErrorKind::OutOfMemory, e))?;
+        buf.resize(size, 0);
+        Self::unfilled(base, buf).first_fill()
+    }
+
+    /// 最初の読み込みのエラーを、作成時ではなく使ったときに返すBufBytesを作成
+    /// 
+    /// [`with_capacity`](Self::with_capacity)と同じく作成時にbaseを1回読みますが、失敗しても`Self`を返します。  
+    /// そのときのエラーは[`get_err`](Self::get_err)に入り、最初の`next()`でわかります。  
+    /// (空のファイルなら、[`with_capacity`](Self::with_capacity)と同じく`ErrorKind::UnexpectedEof`が入ります)  
+    /// 複数のBufBytesを先に準備しておいて、まとめて使うときに便利です。
+    pub fn with_capacity_deferred(base: B, size: usize) -> Self {
+        let mut bytes = Self::unfilled(base, vec![0; size]);
+        if !bytes.refill_buffer() && bytes.error.is_none() {
+            bytes.error = Some(Error::new(std::io::ErrorKind::UnexpectedEof, "0 size file"));
         }
+        bytes
+    }
 
-        // バッファの先頭のポインタを取り出す。 これが、イテレーターのポインタともなる
-        // イテレーターの終わりを判断するため、バッファ最後のポインタもとる
-        let buf_ptr = NonNull::new(buf.as_mut_ptr()).unwrap();
-        let buf_ptr_end = NonNull::new(&mut buf[buf_len-1] as *mut u8).unwrap();
-        // let buf_ptr_end = unsafe { buf_ptr.as_ptr().add(buf_len) };
-        Ok(Self {
+    /// 最初に使われるまで読み込まないBufBytesを作成
+    /// 
+    /// [`with_capacity`](Self::with_capacity)は作成時にbaseを1回読みますが、  
+    /// こちらは最初の`next()`や[`peek`](Self::peek)で初めて読み込みます。  
+    /// そのため、io処理のエラーは作成時ではなく、使ったときに[`get_err`](Self::get_err)に現れます。  
+    /// 空のファイルもエラーにはならず、最初の`next()`が`None`になるだけです。
+    pub fn lazy(base: B, size: usize) -> Self {
+        Self::unfilled(base, vec![0; size])
+    }
+
+    /// スレッドローカルのプールから借りたバッファで、BufBytesを作成
+    /// 
+    /// バッファーサイズは8192です。 BufBytesを捨てると、バッファはプールに返ります。  
+    /// 同じスレッドで何度も作っては捨てるとき、バッファの確保を毎回しなくて済みます。  
+    /// プールが空なら、新しく確保します。
+    pub fn from_pool(base: B) -> Result<Self> {
+        Self::unfilled(base, Buffer::pooled()).first_fill()
+    }
+
+    /// 自分で確保したバッファを使うBufBytesを作成
+    /// 
+    /// bufをそのまま内部のバッファにします。バッファーサイズは`buf.len()`です。  
+    /// (中身は読み込みで上書きされます)  
+    /// 空のVecを渡すと、`InvalidInput`エラーになります。
+    pub fn with_buffer(base: B, buf: Vec<u8>) -> Result<Self> {
+        if buf.is_empty() {
+            return Err(Error::new(std::io::ErrorKind::InvalidInput, "empty buffer"));
+        }
+        Self::unfilled(base, buf).first_fill()
+    }
+
+    /// 先頭のアドレスがalignの倍数になるバッファを持つBufBytesを作成
+    /// 
+    /// SIMDでのスキャンや、アラインを要求するコピーに備えるときに使います。  
+    /// バッファは`std::alloc`で確保し、広げたり縮めたりしてもアラインを保ちます。  
+    /// alignが2のべき乗でなければ`InvalidInput`エラーに、確保できなければ`OutOfMemory`エラーになります。
+    pub fn with_aligned_capacity(base: B, size: usize, align: usize) -> Result<Self> {
+        Self::unfilled(base, Buffer::aligned(size, align)?).first_fill()
+    }
+
+    /// io処理のエラーを先読みで早期に検出するBufBytesを作成
+    /// 
+    /// 通常のBufBytesは、バッファを使い切ってから次のデータを読み込むため、  
+    /// エラーはバッファを全部返した後に見つかります。  
+    /// このモードでは、バッファをもう一つ持ち、次のデータを常に先読みしておきます。  
+    /// 先読みでエラーが起きると、その時点で[`get_err`](Self::get_err)に現れます。  
+    /// (手元のバッファのバイトは、その後も最後まで返します)  
+    /// 常に次を読みに行くので、対話的な入力には向きません。
+    pub fn with_eager_error(base: B, size: usize) -> Result<Self> {
+        let mut bytes = Self::unfilled(base, vec![0; size]);
+        bytes.ahead = Some(vec![0; size].into());
+        bytes.fill_ahead();
+        bytes.first_fill()
+    }
+
+    /// 外からキャンセルできるBufBytesを作成
+    /// 
+    /// tokenが`true`になると、次にbaseから読み込むタイミング(バッファの境界)で  
+    /// `Interrupted`エラーを立てて、イテレーションを終えます。  
+    /// 別スレッドから読み込みループを止めるのに使えます。
+    pub fn with_cancel(base: B, size: usize, token: Arc<AtomicBool>) -> Result<Self> {
+        let mut bytes = Self::unfilled(base, vec![0; size]);
+        bytes.cancel = Some(token);
+        bytes.first_fill()
+    }
+
+    /// 読み込みの様子に合わせてバッファーサイズを変えるBufBytesを作成
+    /// 
+    /// 8192から始めて、読み込みが毎回バッファいっぱいになるなら倍に、  
+    /// 半分にも満たない読み込みが続くなら半分にします。  
+    /// バッファーサイズは1KiBから1MiBの間に収めます。
+    pub fn with_adaptive(base: B) -> Result<Self> {
+        let mut bytes = Self::unfilled(base, vec![0; 8192]);
+        bytes.adaptive = Some(0);
+        bytes.first_fill()
+    }
+
+    /// 読み込みのエラーを再試行するBufBytesを作成
+    /// 
+    /// baseの`read`がエラーを返したら、max_retries回まで読み直します。  
+    /// それでも失敗したときだけ、[`get_err`](Self::get_err)にエラーが入ります。  
+    /// どんな種類のエラーでも再試行します。 種類を絞るときは[`with_retry_on`](Self::with_retry_on)を使ってください。
+    pub fn with_retry(base: B, size: usize, max_retries: usize) -> Result<Self> {
+        let mut bytes = Self::unfilled(base, vec![0; size]);
+        bytes.retry = max_retries;
+        bytes.first_fill()
+    }
+
+    /// 指定した種類の読み込みのエラーだけを再試行するBufBytesを作成
+    /// 
+    /// `Interrupted`や`WouldBlock`のような一時的なエラーだけを再試行し、  
+    /// それ以外のエラーはすぐに[`get_err`](Self::get_err)に入れたいときに使います。
+    pub fn with_retry_on(base: B, size: usize, max_retries: usize, kinds: &[std::io::ErrorKind]) -> Result<Self> {
+        let mut bytes = Self::unfilled(base, vec![0; size]);
+        bytes.retry = max_retries;
+        bytes.retry_kinds = Some(kinds.to_vec());
+        bytes.first_fill()
+    }
+
+    /// primaryが読めなくなったら、fallbackに切り替えて続きを読むBufBytesを作成
+    /// 
+    /// primaryがEOFになるか、エラーを返したら、以降はfallbackから読みます。  
+    /// (エラーは[`get_err`](Self::get_err)には入れず、primaryは捨てます)  
+    /// `Read::chain`はEOFでしか次に進みませんが、こちらはエラーのときも切り替えます。  
+    /// fallbackは自分の今の位置から読むので、続きの位置に合わせてから渡してください。  
+    /// 切り替えは1回だけで、fallbackのエラーは通常どおり扱います。
+    pub fn with_fallback(primary: B, fallback: B, size: usize) -> Result<Self> {
+        let mut bytes = Self::unfilled(primary, vec![0; size]);
+        bytes.fallback = Some(fallback);
+        bytes.first_fill()
+    }
+
+    /// 消費したバイトを覚えておき、巻き戻せるBufBytesを作成
+    /// 
+    /// 最後に消費したcache_limitバイトまでをキャッシュし、[`rewind_by`](Self::rewind_by)で戻れます。  
+    /// パイプのようにSeekできないbaseでも、少しだけ読み直したいときに使います。
+    pub fn with_rewind_cache(base: B, size: usize, cache_limit: usize) -> Result<Self> {
+        let mut bytes = Self::unfilled(base, vec![0; size]);
+        let tracking = bytes.tracking_mut();
+        tracking.rewind_cache = Some(VecDeque::with_capacity(cache_limit));
+        tracking.rewind_limit = cache_limit;
+        bytes.first_fill()
+    }
+
+    /// 消費したバイトをすべて記録するBufBytesを作成
+    /// 
+    /// `next()`や[`consume`](Self::consume)で消費したバイトを、消費した順に[`recorded`](Self::recorded)へためます。  
+    /// [`peek`](Self::peek)などで覗いただけのバイトは記録しません。  
+    /// 記録は消えずに増え続けるので、デバッグや、短い入力の再生に使ってください。
+    pub fn with_recording(base: B, size: usize) -> Result<Self> {
+        let mut bytes = Self::unfilled(base, vec![0; size]);
+        bytes.tracking_mut().recorded = Some(Vec::new());
+        bytes.first_fill()
+    }
+
+    /// [`with_recording`](Self::with_recording)で記録した、消費したバイト
+    /// 
+    /// [`unread`](Self::unread)で押し戻したバイトを読み直したときは、もう一度記録します。  
+    /// 記録していないBufBytesでは空です。
+    pub fn recorded(&self) -> &[u8] {
+        self.tracking.as_ref().and_then(|tracking| tracking.recorded.as_deref()).unwrap_or(&[])
+    }
+
+    /// 直近に消費したバイトだけを、リングに残すBufBytesを作成
+    /// 
+    /// [`with_recording`](Self::with_recording)と違い、ring_capacityバイトを超えると古いバイトから捨てるので、  
+    /// いくら読んでもメモリは増えません。 エラーが起きる直前のデータを調べるのに使えます。
+    pub fn with_ring(base: B, size: usize, ring_capacity: usize) -> Result<Self> {
+        let mut bytes = Self::unfilled(base, vec![0; size]);
+        let tracking = bytes.tracking_mut();
+        tracking.ring = Some(VecDeque::with_capacity(ring_capacity));
+        tracking.ring_capacity = ring_capacity;
+        bytes.first_fill()
+    }
+
+    /// [`with_ring`](Self::with_ring)のリングに残っている、直近に消費したバイト
+    /// 
+    /// 古いものから順に並べて返します。 リングを使っていないBufBytesでは空です。
+    pub fn ring_contents(&self) -> Vec<u8> {
+        self.tracking.as_ref().and_then(|tracking| tracking.ring.as_ref())
+            .map_or_else(Vec::new, |ring| ring.iter().copied().collect())
+    }
+
+    /// 消費したバイトの記録を、なければ作って返す
+    fn tracking_mut(&mut self) -> &mut tracking::Tracking {
+        // 以降のnext()は、毎回track_consumedを通す
+        self.fast_end = NonNull::dangling();
+        self.tracking.get_or_insert_with(Default::default)
+    }
+
+    /// with_fallbackのfallbackにbaseを切り替える
+    fn switch_to_fallback(&mut self) {
+        if let Some(fallback) = self.fallback.take() {
+            self.base = fallback;
+        }
+    }
+
+    /// 読み込めたバイト数に合わせて、次の読み込みのバッファーサイズを変える
+    /// 
+    /// buf_len以降(まだ読み込んでいない部分)だけを伸び縮みさせる
+    fn adapt_buffer(&mut self, buf_len: usize) {
+        let Some(short_reads) = self.adaptive.as_mut() else {
+            return;
+        };
+        let size = self.buf.len();
+        if buf_len == size {
+            *short_reads = 0;
+            self.buf.resize((size * 2).min(ADAPTIVE_MAX), 0);
+        } else if buf_len < size / 2 {
+            *short_reads += 1;
+            if *short_reads >= ADAPTIVE_SHRINK_AFTER {
+                *short_reads = 0;
+                self.buf.truncate((size / 2).max(ADAPTIVE_MIN));
+            }
+        } else {
+            *short_reads = 0;
+        }
+    }
+
+    /// まだ何も読み込んでいない(空のバッファを持つ)BufBytesを作る
+    fn unfilled(base: B, buf: impl Into<Buffer>) -> Self {
+        let mut bytes = Self {
             base,
-            buf,
-            buf_ptr,
-            buf_ptr_end,
+            buf: buf.into(),
+            buf_ptr: NonNull::dangling(),
+            buf_ptr_end: NonNull::dangling(),
+            fast_end: NonNull::dangling(),
+            tracked: Cell::new(NonNull::dangling()),
             // 途中baseからデータを読み込む際にエラーが起きた時は、
             // ここにエラーを入れる
             error: None,
-        })
+            ahead: None,
+            ahead_len: 0,
+            read_total: 0,
+            progress: Arc::new(AtomicU64::new(0)),
+            stream_len: None,
+            cancel: None,
+            adaptive: None,
+            retry: 0,
+            retry_kinds: None,
+            fallback: None,
+            tracking: None,
+            #[cfg(feature = "prometheus")]
+            metrics: None,
+            fill_ratio: 0.0,
+            last_good: 0,
+            xor_acc: Cell::new(0),
+            sum_acc: Cell::new(0),
+            latencies: None,
+            peek_end: 0,
+        };
+        bytes.set_buf_ptr(0);
+        bytes
     }
 
+    /// 最初の読み込みを行う
+    /// 
+    /// io処理でエラーが起きたらそのエラーを、  
+    /// 1バイトも読めなければ(空のファイルなら)`UnexpectedEof`を返す
+    fn first_fill(mut self) -> Result<Self> {
+        if self.refill_buffer() {
+            return Ok(self);
+        }
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Err(Error::new(std::io::ErrorKind::UnexpectedEof, "0 size file")),
+        }
+    }
+
+    /// バッファの先頭からbuf_lenバイトを未消費として、ポインタを張り直す
+    fn set_buf_ptr(&mut self, buf_len: usize) {
+        // バッファの先頭のポインタを取り出す。 これが、イテレーターのポインタともなる
+        // イテレーターの終わりを判断するため、バッファ最後のポインタもとる
+        let start = self.buf.as_mut_ptr();
+        if buf_len == 0 {
+            // 空のときは buf_ptr > buf_ptr_end になるようにしておく
+            // (アドレスの比較にしか使わないので、wrapping_addで良い)
+            self.buf_ptr = NonNull::new(start.wrapping_add(1)).unwrap();
+            self.buf_ptr_end = NonNull::new(start).unwrap();
+        } else {
+            self.buf_ptr = NonNull::new(start).unwrap();
+            self.buf_ptr_end = NonNull::new(unsafe { start.add(buf_len - 1) }).unwrap();
+        }
+        self.tracked.set(self.buf_ptr);
+        self.fast_end = if self.tracking.is_some() { NonNull::dangling() } else { self.buf_ptr_end };
+    }
+
+    /// baseから読み込む。 エラーはself.errorに入れ、0を返す
+    fn read_base(&mut self, buf: &mut [u8]) -> usize {
+        if self.cancel.as_ref().is_some_and(|token| token.load(Ordering::Relaxed)) {
+            self.error = Some(Error::new(std::io::ErrorKind::Interrupted, "cancelled"));
+            return 0;
+        }
+        let mut retries = 0;
+        loop {
+            let started = self.latencies.is_some().then(std::time::Instant::now);
+            let res = self.base.read(buf);
+            if let (Some(latencies), Some(started)) = (self.latencies.as_mut(), started) {
+                latencies.push(started.elapsed());
+            }
+            match res {
+                // with_fallbackなら、EOFやエラーでfallbackに切り替えて読み直す
+                Ok(0) if !buf.is_empty() && self.fallback.is_some() => {
+                    self.switch_to_fallback();
+                    retries = 0;
+                },
+                // 渡した長さより多く読めたと言うReaderは信用しない
+                // (そのままset_buf_ptrするとバッファの外をさしてしまう)
+                Ok(buf_len) if buf_len > buf.len() => {
+                    self.error = Some(Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("reader returned {} bytes for a {} byte buffer", buf_len, buf.len()),
+                    ));
+                    return 0;
+                },
+                Ok(buf_len) => {
+                    self.read_total += buf_len as u64;
+                    self.progress.fetch_add(buf_len as u64, Ordering::Relaxed);
+                    #[cfg(feature = "prometheus")]
+                    if let Some(metrics) = self.metrics.as_ref() {
+                        metrics.on_read(buf_len);
+                    }
+                    return buf_len;
+                },
+                // with_retryなら、決められた回数まで読み直す
+                Err(e) if retries < self.retry && self.retry_kinds.as_ref().is_none_or(|kinds| kinds.contains(&e.kind())) => {
+                    retries += 1;
+                },
+                Err(_) if self.fallback.is_some() => {
+                    self.switch_to_fallback();
+                    retries = 0;
+                },
+                Err(e) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::error!(error = %e, read_total = self.read_total, "BufBytes read failed");
+                    self.error = Some(e);
+                    return 0;
+                },
+            }
+        }
+    }
+
+    /// 先読み用のバッファに次のデータを読み込む
+    fn fill_ahead(&mut self) {
+        if let Some(mut ahead) = self.ahead.take() {
+            self.ahead_len = self.read_base(&mut ahead);
+            self.ahead = Some(ahead);
+        }
+    }
+
+    /// バッファを再読み込みする
+    /// 
+    /// baseの`read`は1回だけ呼び、1バイトでも読めたらバッファが埋まっていなくても返す。  
+    /// (対話的な入力で、次のバイトを待ってブロックしないように)
     fn refill_buffer(&mut self) -> bool {
         // 再読み込みできたらtrueを返す
-        match self.base.read(&mut self.buf) {
-            Ok(0) => false,
-            Ok(buf_len) => {
-                // ポインタを再生成する
-                self.buf_ptr = NonNull::new(self.buf.as_mut_ptr()).unwrap();
-                self.buf_ptr_end = NonNull::new(&mut self.buf[buf_len-1] as *mut u8).unwrap();
-                true
+        self.sync_consumed();
+        let buf_len = match self.ahead.as_mut() {
+            // 先読みモードでは、先読み済みのバッファと入れ替えてから次を先読みする
+            Some(ahead) => {
+                let buf_len = self.ahead_len;
+                if buf_len > 0 {
+                    std::mem::swap(&mut self.buf, ahead);
+                    self.fill_ahead();
+                }
+                buf_len
             },
-            Err(e) => {
-                self.error = Some(e);
-                false
+            None => {
+                let mut buf = std::mem::take(&mut self.buf);
+                let buf_len = self.read_base(&mut buf);
+                self.buf = buf;
+                buf_len
             },
+        };
+        if buf_len == 0 {
+            #[cfg(feature = "tracing")]
+            if self.error.is_none() {
+                tracing::trace!(read_total = self.read_total, "BufBytes reached eof");
+            }
+            return false;
         }
+        #[cfg(feature = "tracing")]
+        tracing::trace!(chunk = buf_len, capacity = self.buf.len(), "BufBytes refilled buffer");
+        #[cfg(feature = "prometheus")]
+        if let Some(metrics) = self.metrics.as_ref() {
+            metrics.on_refill(buf_len, self.buf.len());
+        }
+        self.fill_ratio = buf_len as f64 / self.buf.len() as f64;
+        self.adapt_buffer(buf_len);
+        // ポインタを再生成する
+        self.set_buf_ptr(buf_len);
+        true
+    }
+
+    /// バッファーサイズ
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// 最後の再読み込みで読めたバイト数の、バッファーサイズに対する割合
+    /// 
+    /// バッファーサイズを決める手がかりになります。  
+    /// いつも小さければバッファが大きすぎ、いつも1.0ならもっと大きくしても埋まります。  
+    /// EOFなどで何も読めなかった再読み込みは数えず、その前の値のままです。
+    pub fn fill_ratio(&self) -> f64 {
+        self.fill_ratio
+    }
+
+    /// 最後に消費できたバイトの末尾のオフセット
+    /// 
+    /// io処理でエラーが起きても値は残るので、[`resume_from`](Self::resume_from)で再開する位置に使えます。  
+    /// [`unread`](Self::unread)で戻しても、この値は戻りません。 (戻した分を読み直しても、越えるまでは同じ値です)  
+    /// seekで読み取り位置を変えたときだけ、その位置になります。
+    pub fn last_good_position(&self) -> u64 {
+        self.last_good.max(Self::position(self))
+    }
+
+    /// 読み取り位置(これまでに消費したバイト数)を返す
+    /// 
+    /// baseから読み込んだバイト数から、まだ返していないバイト数を引いたものです。  
+    /// `&mut BufBytes`から呼ぶと`Iterator::position`が選ばれてしまうので、  
+    /// そのときは`BufBytes::position(&bytes)`のように呼んでください。
+    pub fn position(&self) -> u64 {
+        // unreadで読み込んだ以上のバイトを戻されることがあるので、飽和させる
+        self.read_total.saturating_sub((self.buffer().len() + self.ahead_len) as u64)
+    }
+
+    /// 未消費のバッファのスライス
+    /// 
+    /// io処理は起こしません。 中身を見るだけなら、[`snapshot`](Self::snapshot)も使えます。
+    pub fn buffer(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.buf_ptr.as_ptr(), self.buffered_len()) }
+    }
+
+    /// バッファに残っている未消費のバイト数
+    /// 
+    /// io処理を起こさず、定数時間で返します。 バッファを全部消費した直後は0です。  
+    /// ([`with_eager_error`](Self::with_eager_error)の先読み分は含みません)
+    pub fn buffered_len(&self) -> usize {
+        // buf_ptr_endはバッファ最後のバイトをさすので+1する
+        // 全部消費するとbuf_ptr > buf_ptr_endになるので、飽和させる
+        (self.buf_ptr_end.as_ptr().addr() + 1).saturating_sub(self.buf_ptr.as_ptr().addr())
+    }
+
+    /// バッファからnバイト消費する
+    /// 
+    /// [`fill_buf_at_least`](Self::fill_buf_at_least)などで覗いたバイトを読み進めるのに使います。  
+    /// 未消費のバイト数より大きいnは、バッファに残っている分までに切り詰めます。
+    pub fn consume(&mut self, n: usize) {
+        let n = n.min(self.buffer().len());
+        // self.bufを借用したままほかのフィールドを更新するので、ポインタから作る
+        let consumed = unsafe { std::slice::from_raw_parts(self.buf_ptr.as_ptr(), n) };
+        self.buf_ptr = unsafe { self.buf_ptr.add(n) };
+        self.track_consumed(consumed);
+    }
+
+    /// 消費したバイトを、巻き戻し用のキャッシュや記録、on_intervalのコールバックに反映する
+    /// 
+    /// `next()`と`consume`の両方から、buf_ptrを進めてから呼ぶ  
+    /// (パリティなどは、あとで`sync_consumed`がまとめて畳み込む)
+    fn track_consumed(&mut self, consumed: &[u8]) {
+        // 使っていなければ、読み取り位置も求めない
+        let Some(mut tracking) = self.tracking.take() else {
+            return;
+        };
+        if let Some(hook) = tracking.interval.as_mut() {
+            hook.reached(Self::position(self));
+        }
+        tracking.push(consumed);
+        self.tracking = Some(tracking);
+    }
+
+    /// 消費したが、まだxor_accなどに畳み込んでいないバイト
+    fn untracked(&self) -> &[u8] {
+        let tracked = self.tracked.get();
+        let len = self.buf_ptr.as_ptr().addr() - tracked.as_ptr().addr();
+        unsafe { std::slice::from_raw_parts(tracked.as_ptr(), len) }
+    }
+
+    /// まだ畳み込んでいない消費済みのバイトを、xor_accとsum_accに畳み込む
+    /// 
+    /// trackedをbuf_ptrまで進めるので、同じバイトを2度畳み込むことはない
+    fn fold_consumed(&self) {
+        let consumed = self.untracked();
+        self.xor_acc.set(consumed.iter().fold(self.xor_acc.get(), |acc, b| acc ^ b));
+        self.sum_acc.set(consumed.iter().fold(self.sum_acc.get(), |acc, &b| acc.wrapping_add(b)));
+        self.tracked.set(self.buf_ptr);
+    }
+
+    /// 消費したバイトを、まとめてxor_accやsum_acc、last_goodに畳み込む
+    /// 
+    /// `next()`で1バイトずつ畳み込むと遅いので、バッファを書き換える前(再読み込みや`compact`)に呼ぶ
+    fn sync_consumed(&mut self) {
+        self.fold_consumed();
+        self.last_good = self.last_good_position();
+    }
+
+    /// `next()`の遅い方
+    /// 
+    /// バッファが空なら再読み込みし、返すバイトがなければfalseを返す。  
+    /// 消費したバイトを記録するときは、これから返すバイトをtrack_consumedに渡しておく
+    #[inline(never)]
+    fn next_slow(&mut self) -> bool {
+        if self.buf_ptr.as_ptr() > self.buf_ptr_end.as_ptr() && !self.refill_buffer() {
+            return false;
+        }
+        if self.tracking.is_some() {
+            // track_consumedは消費した後の読み取り位置を使うので、一度進めてから戻す
+            let next = unsafe { *self.buf_ptr.as_ref() };
+            self.buf_ptr = unsafe { self.buf_ptr.add(1) };
+            self.track_consumed(&[next]);
+            self.buf_ptr = unsafe { self.buf_ptr.sub(1) };
+        }
+        true
+    }
+
+    /// 残りのバイトを、バッファのスライスごとにfで畳み込む
+    /// 
+    /// 1バイトずつ`next()`を呼ぶより速い。 最後まで消費する
+    fn fold_chunks<T>(&mut self, init: T, mut f: impl FnMut(T, &[u8]) -> T) -> T {
+        let mut acc = init;
+        loop {
+            if self.buffer().is_empty() && !self.refill_buffer() {
+                return acc;
+            }
+            acc = f(acc, self.buffer());
+            self.consume(self.buffer().len());
+        }
+    }
+
+    /// 未消費のバイトをバッファの先頭に寄せ、そのバイト数を返す
+    fn compact(&mut self) -> usize {
+        self.sync_consumed();
+        let len = self.buffer().len();
+        if len > 0 {
+            let offset = self.buf_ptr.as_ptr().addr() - self.buf.as_ptr().addr();
+            self.buf.copy_within(offset..offset + len, 0);
+        }
+        self.set_buf_ptr(len);
+        len
+    }
+
+    /// 未消費のバイトを残したまま、バッファを必要最小限まで縮める
+    /// 
+    /// 大きなバッファで作ったあと、短い読み込みしか来ないとわかったときなどに使います。  
+    /// 縮めた後のバッファは未消費のバイト数(最低1バイト)になり、以降はその大きさずつ読み込みます。
+    pub fn shrink_buffer(&mut self) -> Result<()> {
+        let len = self.compact();
+        self.buf.truncate(len.max(1));
+        self.buf.shrink_to_fit();
+        // 再確保でアドレスが変わるかもしれないので、ポインタを張り直す
+        self.set_buf_ptr(len);
+        if let Some(ahead) = self.ahead.as_mut() {
+            ahead.truncate(self.ahead_len.max(1));
+            ahead.shrink_to_fit();
+        }
+        Ok(())
+    }
+
+    /// バイト列を押し戻す
+    /// 
+    /// 次の`next()`から、bytesが先頭から順に返り、その後に今までの続きが返ります。  
+    /// 読みすぎたバイトを戻すのに使います。(読み取り位置もbytesの分だけ戻ります)  
+    /// 読んだバイトと違う値を戻しても構いません。
+    pub fn unread(&mut self, bytes: &[u8]) -> Result<()> {
+        // 未消費のバイトを後ろにずらして、空いた先頭にbytesを入れる
+        let len = self.compact();
+        let n = bytes.len();
+        if self.buf.len() < n + len {
+            self.buf.resize(n + len, 0);
+        }
+        self.buf.copy_within(..len, n);
+        self.buf[..n].copy_from_slice(bytes);
+        self.set_buf_ptr(n + len);
+        // 押し戻したバイトは、消費していないことにする
+        self.xor_acc.set(bytes.iter().fold(self.xor_acc.get(), |acc, b| acc ^ b));
+        self.sum_acc.set(bytes.iter().fold(self.sum_acc.get(), |acc, &b| acc.wrapping_sub(b)));
+        Ok(())
+    }
+
+    /// 消費したバイトをnバイト巻き戻す
+    /// 
+    /// [`with_rewind_cache`](Self::with_rewind_cache)で作ったときだけ使えます。  
+    /// キャッシュに残っている分(最大cache_limitバイト)より多くは戻れず、`InvalidInput`エラーになります。  
+    /// (キャッシュを持たないBufBytesでは、n > 0なら常にエラーです)
+    pub fn rewind_by(&mut self, n: usize) -> Result<()> {
+        let cached = self.tracking.as_ref().and_then(|tracking| tracking.rewind_cache.as_ref()).map_or(0, VecDeque::len);
+        if n > cached {
+            return Err(Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("cannot rewind {} bytes, only {} bytes cached", n, cached),
+            ));
+        }
+        let Some(cache) = self.tracking.as_mut().and_then(|tracking| tracking.rewind_cache.as_mut()) else {
+            return Ok(());
+        };
+        let bytes: Vec<u8> = cache.drain(cached - n..).collect();
+        // zeroize featureなら、押し戻したあとの一時的なコピーも消す
+        #[cfg(feature = "zeroize")]
+        let bytes = zeroize::Zeroizing::new(bytes);
+        self.unread(&bytes)
+    }
+
+    /// 1バイト押し戻す
+    /// 
+    /// [`unread`](Self::unread)の1バイト版です。
+    pub fn unread_byte(&mut self, byte: u8) -> Result<()> {
+        self.unread(&[byte])
+    }
+
+    /// 未消費のバイトがn以上連続するようにバッファを読み足す
+    /// 
+    /// 未消費のバイトをバッファの先頭に寄せてから、後ろに読み込みます。  
+    /// nがバッファより大きいときは、バッファを広げます。  
+    /// EOFかエラーで足りなくなったときは、読めた分だけになります。
+    fn fill_at_least(&mut self, n: usize) {
+        if self.buffer().len() >= n {
+            return;
+        }
+        let mut len = self.compact();
+        let mut buf = std::mem::take(&mut self.buf);
+        if buf.len() < n {
+            buf.resize(n, 0);
+        }
+        while len < n {
+            match self.ahead.take() {
+                // 先読みモードでは、先読み済みのデータを後ろにつなげて、次を先読みする
+                Some(ahead) => {
+                    let ahead_len = self.ahead_len;
+                    if buf.len() < len + ahead_len {
+                        buf.resize(len + ahead_len, 0);
+                    }
+                    buf[len..len + ahead_len].copy_from_slice(&ahead[..ahead_len]);
+                    len += ahead_len;
+                    self.ahead = Some(ahead);
+                    self.fill_ahead();
+                    if ahead_len == 0 {
+                        break;
+                    }
+                },
+                None => {
+                    let buf_len = self.read_base(&mut buf[len..]);
+                    if buf_len == 0 {
+                        break;
+                    }
+                    len += buf_len;
+                },
+            }
+        }
+        self.buf = buf;
+        self.set_buf_ptr(len);
+    }
+
+    /// nバイト読み飛ばし、飛ばせたバイト数を返す
+    /// 
+    /// 1バイトずつではなく、バッファ単位でポインタを進めます。  
+    /// EOFかエラーになったときは、nより小さい値になります。
+    fn skip_bytes(&mut self, n: u64) -> u64 {
+        let mut skipped = 0;
+        while skipped < n {
+            if self.buffer().is_empty() && !self.refill_buffer() {
+                break;
+            }
+            let len = (self.buffer().len() as u64).min(n - skipped);
+            self.consume(len as usize);
+            skipped += len;
+        }
+        skipped
+    }
+
+    /// データが足りなかったときに返すエラーを作る
+    /// 
+    /// io処理でエラーが起きていれば、それと同じ種類のエラーを、  
+    /// そうでなければ(EOFなら)`UnexpectedEof`を返します。
+    fn short_read_error(&self) -> Error {
+        match &self.error {
+            Some(e) => Error::new(e.kind(), e.to_string()),
+            None => Error::from(std::io::ErrorKind::UnexpectedEof),
+        }
+    }
+
+    /// baseを取り出す
+    /// 
+    /// まだ消費していないバッファのバイトは捨てられます。  
+    /// `zeroize` featureなら、捨てるバッファや消費したバイトの記録は、0で上書きしてから解放します。
+    pub fn into_inner(self) -> B {
+        self.base
     }
 
     /// io操作中に生じたエラーを取得する
+    /// 
+    /// エラーが起きても、それまでにバッファへ読み込めていたバイトは捨てません。  
+    /// ([`with_eager_error`](Self::with_eager_error)や[`peek_n`](Self::peek_n)で先に読みに行ったときも)  
+    /// `next()`は残りのバイトを全部返してから`None`になります。
+    #[allow(clippy::needless_lifetimes)]
     pub fn get_err<'a>(&'a self) -> &'a Option<std::io::Error> {
         &self.error
     }
@@ -94,6 +854,21 @@ where
     }
 }
 
+/// baseの型を消したBufBytes
+/// 
+/// ファイルやソケット、`Cursor`などを、同じ型の変数で扱えます。 [`BufBytes::boxed`]で作成します。
+pub type BoxedBufBytes = BufBytes<Box<dyn Read>>;
+
+impl BoxedBufBytes {
+    /// baseを`Box<dyn Read>`にして、BufBytesを作成
+    /// 
+    /// 実行時に読み込み元を切り替えるときに使えます。 読み込みは動的ディスパッチになります。  
+    /// バッファーサイズは[`new`](Self::new)と同じ8192で、空のファイルは`ErrorKind::UnexpectedEof`のエラーになります。
+    pub fn boxed(base: impl Read + 'static) -> Result<Self> {
+        Self::new(Box::new(base))
+    }
+}
+
 impl<B> Iterator for BufBytes<B>
 where
     B: Read,
@@ -101,20 +876,37 @@ where
     type Item = u8;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.buf_ptr.as_ptr() > self.buf_ptr_end.as_ptr() {
-            if !self.refill_buffer() {
-                return None;
-            }
+        // バッファが空のときと、消費したバイトを記録するときだけ、遅い方へ行く
+        if self.buf_ptr.as_ptr() > self.fast_end.as_ptr() && !self.next_slow() {
+            return None;
         }
         unsafe {
-            let res = self.buf_ptr.as_ref();
+            let res = *self.buf_ptr.as_ref();
             self.buf_ptr = self.buf_ptr.add(1);
-            Some(*res)
+            Some(res)
         }
     }
+
+    /// 最大のバイトを、バッファごとにまとめて探す
+    /// 
+    /// io処理でエラーが起きたら、そこまでの最大を返します。(エラーは[`get_err`](Self::get_err)に残ります)
+    fn max(mut self) -> Option<Self::Item> {
+        self.fold_chunks(None, |acc, buf| acc.max(buf.iter().max().copied()))
+    }
+
+    /// 最小のバイトを、バッファごとにまとめて探す
+    /// 
+    /// io処理でエラーが起きたら、そこまでの最小を返します。(エラーは[`get_err`](Self::get_err)に残ります)
+    fn min(mut self) -> Option<Self::Item> {
+        self.fold_chunks(None, |acc: Option<u8>, buf| match (acc, buf.iter().min().copied()) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        })
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::unused_io_amount, clippy::let_unit_value, clippy::redundant_pattern_matching)]
 mod tests {
     use tempfile::NamedTempFile;
     use std::io::{Seek, Write};
@@ -143,6 +935,54 @@ mod tests {
         }
     }
 
+    // 入力された分だけ返し、まだ入力がないのにreadされたらpanicする仮想ファイル
+    // (本物の対話的な入力なら、そこでブロックしてしまう)
+    struct InteractiveFile {
+        typed: std::rc::Rc<std::cell::RefCell<Vec<u8>>>,
+    }
+
+    impl Read for InteractiveFile {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let mut typed = self.typed.borrow_mut();
+            assert!(!typed.is_empty(), "read would block");
+            let n = typed.len().min(buf.len());
+            buf[..n].copy_from_slice(&typed[..n]);
+            typed.drain(..n);
+            Ok(n)
+        }
+    }
+
+    // 1回のreadで最大chunkバイトしか返さない仮想ファイル
+    struct ChunkFile {
+        chunk: usize,
+    }
+
+    impl Read for ChunkFile {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let n = self.chunk.min(buf.len());
+            buf[..n].fill(0);
+            Ok(n)
+        }
+    }
+
+    // failsの回数だけエラーを返してから、dataを返す仮想ファイル
+    #[derive(Debug)]
+    struct FlakyFile {
+        data: &'static [u8],
+        fails: usize,
+        kind: std::io::ErrorKind,
+    }
+
+    impl Read for FlakyFile {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            if self.fails > 0 {
+                self.fails -= 1;
+                return Err(Error::new(self.kind, "flaky"));
+            }
+            self.data.read(buf)
+        }
+    }
+
     // 8byte バッファーでデータを読み込む
     #[test]
     fn buf_8byte_test() {
@@ -169,7 +1009,7 @@ mod tests {
         let file = NamedTempFile::new().unwrap();
         let bytes = BufBytes::new(file);
 
-        assert!(matches!(bytes, Err(_)));
+        assert_eq!(bytes.unwrap_err().kind(), std::io::ErrorKind::UnexpectedEof);
     }
 
     // リード中にエラーが起きたときの動作
@@ -198,6 +1038,554 @@ mod tests {
 
     }
 
+    // 通常モードでは、エラーはバッファを全部返した後に見つかる
+    #[test]
+    fn lazy_error_timing_test() {
+        // 17byte目を読み込もうとするとエラーが返ってくる仮想ファイル
+        let err_file = ErrorFile::new(17);
+        let mut bytes = BufBytes::with_capacity(err_file, 8).unwrap();
+
+        // 9byte目を読んだ時点(2回目の読み込み)ではまだエラーはない
+        bytes.by_ref().take(9).for_each(drop);
+        assert!(bytes.get_err().is_none());
+
+        // 16byte分返しきってから、エラーが見つかる
+        assert_eq!(bytes.by_ref().count(), 7);
+        assert!(bytes.get_err().is_some());
+    }
+
+    // 先読みモードでは、エラーはバッファを返している途中で見つかる
+    #[test]
+    fn eager_error_timing_test() {
+        let err_file = ErrorFile::new(17);
+        let mut bytes = BufBytes::with_eager_error(err_file, 8).unwrap();
+
+        // 最初のバッファを読んでいる間は、まだエラーはない
+        bytes.by_ref().take(8).for_each(drop);
+        assert!(bytes.get_err().is_none());
+
+        // 2つ目のバッファに入った時点で、3回目の読み込み(先読み)のエラーが見つかる
+        assert!(bytes.next().is_some());
+        assert!(bytes.get_err().is_some());
+
+        // 手元に残っているバイトは最後まで返す
+        assert_eq!(bytes.count(), 7);
+    }
+
+    // 先読みモードでも、正常なファイルは全部読める
+    #[test]
+    fn eager_error_success_test() {
+        let base_txt = "abcdefg\nhijklmn\nopqrstu\nvwxyz00\n";
+        let bytes = BufBytes::with_eager_error(base_txt.as_bytes(), 8).unwrap();
+
+        assert_eq!(bytes.collect::<Vec<u8>>(), base_txt.as_bytes());
+    }
+
+    // 大きなバッファを縮めても、読み取りを続けられる
+    #[test]
+    fn shrink_buffer_test() {
+        let base: Vec<u8> = (0..100).collect();
+        let mut bytes = BufBytes::with_capacity(&base[..], 64).unwrap();
+        bytes.by_ref().take(60).for_each(drop);
+
+        bytes.shrink_buffer().unwrap();
+        // 未消費の4byteだけになる
+        assert!(bytes.buf.capacity() < 64);
+        assert_eq!(bytes.buf.len(), 4);
+        // 残りは4byteずつ再読み込みしながら読める
+        assert_eq!(bytes.collect::<Vec<u8>>(), &base[60..]);
+    }
+
+    // moveしてもポインタが壊れない
+    #[test]
+    fn move_test() {
+        let base: Vec<u8> = (0..32).collect();
+        let mut bytes = BufBytes::with_capacity(&base[..], 8).unwrap();
+        bytes.by_ref().take(3).for_each(drop);
+
+        fn pass<T>(t: T) -> T {
+            t
+        }
+
+        // Box、Vec、関数の引数と戻り値とmoveを繰り返しても、続きから読める
+        let mut boxed = Box::new(bytes);
+        assert_eq!(boxed.next(), Some(3));
+        let mut list = vec![*boxed];
+        assert_eq!(list[0].next(), Some(4));
+        let moved = pass(list.pop().unwrap());
+        assert_eq!(moved.collect::<Vec<u8>>(), &base[5..]);
+    }
+
+    // 2つのBufBytesを入れ替えても、それぞれの続きが読める
+    #[test]
+    fn swap_test() {
+        let mut a = BufBytes::with_capacity(&b"aaaaaaaa"[..], 4).unwrap();
+        let mut b = BufBytes::with_capacity(&b"bbbb"[..], 4).unwrap();
+        a.next();
+
+        std::mem::swap(&mut a, &mut b);
+        assert_eq!(a.count(), 4);
+        assert_eq!(b.count(), 7);
+    }
+
+    // 1byteずつ入力されても、バッファが埋まるのを待たずに返す
+    #[test]
+    fn interactive_read_test() {
+        let typed = std::rc::Rc::new(std::cell::RefCell::new(b"a".to_vec()));
+        let mut bytes = BufBytes::new(InteractiveFile { typed: typed.clone() }).unwrap();
+        assert_eq!(bytes.next(), Some(b'a'));
+
+        for b in b"bcd\n" {
+            typed.borrow_mut().push(*b);
+            assert_eq!(bytes.next(), Some(*b));
+        }
+    }
+
+    // 別スレッドでキャンセルすると、次のバッファ境界で止まる
+    #[test]
+    fn cancel_test() {
+        let base = vec![0u8; 1000];
+        let token = Arc::new(AtomicBool::new(false));
+        let mut bytes = BufBytes::with_cancel(&base[..], 8, token.clone()).unwrap();
+        bytes.by_ref().take(20).for_each(drop);
+
+        std::thread::spawn(move || token.store(true, Ordering::Relaxed)).join().unwrap();
+
+        // バッファに残っていた4byteだけ返して終わる
+        assert_eq!(bytes.by_ref().count(), 4);
+        assert_eq!(bytes.get_err().as_ref().unwrap().kind(), std::io::ErrorKind::Interrupted);
+    }
+
+    // next()を呼ぶごとに減り、全部消費すると0になる
+    #[test]
+    fn buffered_len_test() {
+        let base: Vec<u8> = (0..10).collect();
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+        assert_eq!(bytes.buffered_len(), 4);
+
+        bytes.next();
+        assert_eq!(bytes.buffered_len(), 3);
+        bytes.by_ref().take(3).for_each(drop);
+        assert_eq!(bytes.buffered_len(), 0);
+        assert!(bytes.buffer().is_empty());
+
+        // 再読み込みすると、また増える
+        bytes.next();
+        assert_eq!(bytes.buffered_len(), 3);
+        assert_eq!(bytes.buffer(), &[5, 6, 7]);
+    }
+
+    // 押し戻したバイトが、逆順にならずに先に返る
+    #[test]
+    fn unread_test() {
+        let base: Vec<u8> = (0..10).collect();
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+        bytes.by_ref().take(3).for_each(drop);
+
+        bytes.unread(&[0xa0, 0xa1, 0xa2, 0xa3, 0xa4]).unwrap();
+        bytes.unread_byte(0xff).unwrap();
+        assert_eq!(bytes.position(), 0);
+        assert_eq!(bytes.collect::<Vec<u8>>(), [0xff, 0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    // 読んだバイトを戻して読み直す
+    #[test]
+    fn unread_reread_test() {
+        let base = b"abcdefgh";
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+        let read: Vec<u8> = bytes.by_ref().take(6).collect();
+
+        bytes.unread(&read[4..]).unwrap();
+        assert_eq!(bytes.position(), 4);
+        assert_eq!(bytes.collect::<Vec<u8>>(), b"efgh");
+    }
+
+    // 巨大なサイズでもpanicせず、OutOfMemoryが返る
+    #[test]
+    fn try_with_capacity_test() {
+        let base = b"abc";
+        let err = BufBytes::try_with_capacity(&base[..], usize::MAX).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::OutOfMemory);
+
+        let bytes = BufBytes::try_with_capacity(&base[..], 2).unwrap();
+        assert_eq!(bytes.collect::<Vec<u8>>(), base);
+    }
+
+    // 先読みでエラーになっても、残りのバイトを捨てずに返してからNoneになる
+    #[test]
+    fn eager_error_keeps_buffer_test() {
+        // 9byte目を読み込もうとするとエラーが返ってくる仮想ファイル
+        let err_file = ErrorFile::new(9);
+        let mut bytes = BufBytes::with_eager_error(err_file, 8).unwrap();
+
+        // 作った時点で先読みのエラーが見つかっているが、最初の8byteは全部読める
+        assert!(bytes.get_err().is_some());
+        assert_eq!(bytes.buffered_len(), 8);
+        assert_eq!(bytes.by_ref().count(), 8);
+        assert_eq!(bytes.next(), None);
+    }
+
+    // peekでエラーになっても、バッファにあったバイトは読める
+    #[test]
+    fn peek_error_keeps_buffer_test() {
+        let err_file = ErrorFile::new(17);
+        let mut bytes = BufBytes::with_capacity(err_file, 8).unwrap();
+        bytes.by_ref().take(3).for_each(drop);
+
+        // 読み足しがエラーになるので、20byteの先読みはエラー
+        assert!(bytes.peek_n(20).is_err());
+        // それでも、最初に読み込んだ残りの5byteは読める
+        assert_eq!(bytes.buffered_len(), 5);
+        assert_eq!(bytes.count(), 5);
+    }
+
+    // 大量のデータを返すReaderでは、上限までバッファが成長する
+    #[test]
+    fn adaptive_grow_test() {
+        let base = vec![0u8; 4 << 20];
+        let mut bytes = BufBytes::with_adaptive(&base[..]).unwrap();
+        assert_eq!(bytes.buf.len(), 16384);
+
+        bytes.by_ref().take(1 << 20).for_each(drop);
+        assert_eq!(bytes.buf.len(), ADAPTIVE_MAX);
+        assert_eq!(bytes.count(), 3 << 20);
+    }
+
+    // 少量ずつ返すReaderでは成長せず、下限まで縮む
+    #[test]
+    fn adaptive_shrink_test() {
+        let mut bytes = BufBytes::with_adaptive(ChunkFile { chunk: 100 }).unwrap();
+        // 10回読み込んだので、4回目と8回目で縮む
+        bytes.by_ref().take(1000).for_each(drop);
+        assert_eq!(bytes.buf.len(), 2048);
+
+        bytes.by_ref().take(100000).for_each(drop);
+        assert_eq!(bytes.buf.len(), ADAPTIVE_MIN);
+    }
+
+    // 渡したバッファがそのまま使われる
+    #[test]
+    fn with_buffer_test() {
+        let base: Vec<u8> = (0..100).collect();
+        let buf = vec![0xff; 16];
+        let buf_addr = buf.as_ptr();
+        let bytes = BufBytes::with_buffer(&base[..], buf).unwrap();
+
+        assert_eq!(bytes.capacity(), 16);
+        assert_eq!(bytes.buf.as_ptr(), buf_addr);
+        assert_eq!(bytes.collect::<Vec<u8>>(), base);
+
+        let err = BufBytes::with_buffer(&base[..], Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    // N回失敗してもN回まで再試行すれば全部読める
+    #[test]
+    fn with_retry_test() {
+        let base = FlakyFile { data: b"retry me", fails: 3, kind: std::io::ErrorKind::Other };
+        let mut bytes = BufBytes::with_retry(base, 4, 3).unwrap();
+        assert_eq!(bytes.by_ref().collect::<Vec<u8>>(), b"retry me");
+        assert!(bytes.get_err().is_none());
+
+        // 再試行が足りなければエラーになる
+        let base = FlakyFile { data: b"retry me", fails: 3, kind: std::io::ErrorKind::Other };
+        assert!(BufBytes::with_retry(base, 4, 2).is_err());
+    }
+
+    // 指定した種類のエラーだけ再試行する
+    #[test]
+    fn with_retry_on_test() {
+        let kinds = [std::io::ErrorKind::Interrupted, std::io::ErrorKind::WouldBlock];
+        let base = FlakyFile { data: b"abc", fails: 2, kind: std::io::ErrorKind::WouldBlock };
+        let bytes = BufBytes::with_retry_on(base, 4, 5, &kinds).unwrap();
+        assert_eq!(bytes.collect::<Vec<u8>>(), b"abc");
+
+        let base = FlakyFile { data: b"abc", fails: 1, kind: std::io::ErrorKind::PermissionDenied };
+        let err = BufBytes::with_retry_on(base, 4, 5, &kinds).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+    }
+
+    // バッファ単位のmax/minが、1バイトずつ比べたものと一致する
+    #[test]
+    fn max_min_test() {
+        let base: Vec<u8> = (0..1000u32).map(|i| (i * 37 % 251) as u8 + 3).collect();
+        let naive_max = base.iter().copied().fold(0, u8::max);
+        let naive_min = base.iter().copied().fold(255, u8::min);
+
+        assert_eq!(BufBytes::with_capacity(&base[..], 7).unwrap().max(), Some(naive_max));
+        assert_eq!(BufBytes::with_capacity(&base[..], 7).unwrap().min(), Some(naive_min));
+        assert_eq!(BufBytes::new(&b"\x05\x01\xfe"[..]).unwrap().max(), Some(0xfe));
+        assert_eq!(BufBytes::new(&b"\x05\x01\xfe"[..]).unwrap().min(), Some(0x01));
+    }
+
+    // primaryのEOFで切り替えても、境界のバイトが欠けたり重なったりしない
+    #[test]
+    fn with_fallback_eof_test() {
+        let bytes = BufBytes::with_fallback(&b"abc"[..], &b"defgh"[..], 2).unwrap();
+        assert_eq!(bytes.collect::<Vec<u8>>(), b"abcdefgh");
+    }
+
+    // primaryのエラーでも切り替わり、エラーは残らない
+    #[test]
+    fn with_fallback_error_test() {
+        let broken = FlakyFile { data: b"", fails: usize::MAX, kind: std::io::ErrorKind::Other };
+        let primary: Box<dyn Read> = Box::new((&b"cached"[..]).chain(broken));
+        let fallback: Box<dyn Read> = Box::new(&b" origin"[..]);
+        let mut bytes = BufBytes::with_fallback(primary, fallback, 4).unwrap();
+        assert_eq!(bytes.by_ref().collect::<Vec<u8>>(), b"cached origin");
+        assert!(bytes.get_err().is_none());
+    }
+
+    // 作っただけでは読み込まず、最初のnext()で読み込む
+    #[test]
+    fn lazy_test() {
+        let typed = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        // 入力がないのに読み込むとpanicする
+        let mut bytes = BufBytes::lazy(InteractiveFile { typed: typed.clone() }, 8);
+        assert_eq!(BufBytes::position(&bytes), 0);
+
+        typed.borrow_mut().extend_from_slice(b"hi");
+        assert_eq!(bytes.next(), Some(b'h'));
+        assert_eq!(bytes.next(), Some(b'i'));
+
+        // エラーや空のファイルは、使ったときに現れる
+        let mut bytes = BufBytes::lazy(ErrorFile::new(0), 8);
+        assert_eq!(bytes.next(), None);
+        assert!(bytes.get_err().is_some());
+        assert_eq!(BufBytes::lazy(&b""[..], 8).next(), None);
+    }
+
+    // tracingを有効にすると、再読み込み・EOF・エラーがイベントになる
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tracing_events_test() {
+        use std::sync::Mutex;
+        use tracing::{Event, Metadata, span};
+
+        // イベントのメッセージとレベルを集めるだけのSubscriber
+        #[derive(Default)]
+        struct Collect(Arc<Mutex<Vec<(tracing::Level, String)>>>);
+
+        impl tracing::Subscriber for Collect {
+            fn enabled(&self, _: &Metadata<'_>) -> bool { true }
+            fn new_span(&self, _: &span::Attributes<'_>) -> span::Id { span::Id::from_u64(1) }
+            fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+            fn event(&self, event: &Event<'_>) {
+                let mut message = String::new();
+                event.record(&mut |field: &tracing::field::Field, value: &dyn std::fmt::Debug| {
+                    if field.name() == "message" {
+                        message = format!("{:?}", value);
+                    }
+                });
+                self.0.lock().unwrap().push((*event.metadata().level(), message));
+            }
+            fn enter(&self, _: &span::Id) {}
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        tracing::subscriber::with_default(Collect(events.clone()), || {
+            BufBytes::with_capacity(&b"abcdef"[..], 4).unwrap().for_each(drop);
+            let mut bytes = BufBytes::with_capacity(ErrorFile::new(4), 4).unwrap();
+            bytes.by_ref().for_each(drop);
+        });
+        let events = events.lock().unwrap();
+        let count = |level, message: &str| events.iter().filter(|(l, m)| *l == level && m == message).count();
+        assert_eq!(count(tracing::Level::TRACE, "BufBytes refilled buffer"), 3);
+        assert_eq!(count(tracing::Level::TRACE, "BufBytes reached eof"), 1);
+        assert_eq!(count(tracing::Level::ERROR, "BufBytes read failed"), 1);
+    }
+
+    // バッファより大きい長さを返すReaderは、panicやUBではなくInvalidDataになる
+    #[test]
+    fn lying_reader_test() {
+        // lie_afterバイトまでは正しく読み、その後はバッファより1大きい長さを返す
+        #[derive(Debug)]
+        struct LyingReader {
+            lie_after: usize,
+        }
+        impl Read for LyingReader {
+            fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+                if self.lie_after == 0 {
+                    return Ok(buf.len() + 1);
+                }
+                let n = self.lie_after.min(buf.len());
+                self.lie_after -= n;
+                buf[..n].fill(b'x');
+                Ok(n)
+            }
+        }
+
+        let err = BufBytes::with_capacity(LyingReader { lie_after: 0 }, 4).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        let mut bytes = BufBytes::with_capacity(LyingReader { lie_after: 6 }, 4).unwrap();
+        assert_eq!(bytes.by_ref().count(), 6);
+        assert_eq!(bytes.get_err().as_ref().unwrap().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    // Seekできないbaseでも、キャッシュの範囲なら巻き戻して読み直せる
+    #[test]
+    fn rewind_by_test() {
+        let base: Vec<u8> = (0..20).collect();
+        let mut bytes = BufBytes::with_rewind_cache(&base[..], 4, 6).unwrap();
+
+        bytes.by_ref().take(5).for_each(drop);
+        bytes.rewind_by(3).unwrap();
+        assert_eq!(BufBytes::position(&bytes), 2);
+        assert_eq!(bytes.by_ref().take(4).collect::<Vec<u8>>(), [2, 3, 4, 5]);
+
+        // consumeでまとめて読み進めても、最後の6バイトだけが残る
+        bytes.fill_buf_at_least(8).unwrap();
+        bytes.consume(8);
+        assert!(bytes.rewind_by(7).is_err());
+        bytes.rewind_by(6).unwrap();
+        assert_eq!(bytes.collect::<Vec<u8>>(), (8..20).collect::<Vec<u8>>());
+    }
+
+    // キャッシュがなければ戻れない
+    #[test]
+    fn rewind_by_without_cache_test() {
+        let mut bytes = BufBytes::new(&b"abc"[..]).unwrap();
+        bytes.next();
+        let err = bytes.rewind_by(1).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(bytes.rewind_by(0).is_ok());
+    }
+
+    // バッファの先頭がalignの倍数になり、広げても縮めても保たれる
+    #[test]
+    fn with_aligned_capacity_test() {
+        let base: Vec<u8> = (0..200).collect();
+        for align in [1, 64, 4096] {
+            let mut bytes = BufBytes::with_aligned_capacity(&base[..], 16, align).unwrap();
+            assert_eq!(bytes.buf.as_ptr().addr() % align, 0);
+            assert_eq!(bytes.capacity(), 16);
+
+            assert_eq!(bytes.peek_n(40).unwrap(), &base[..40]);
+            assert_eq!(bytes.buf.as_ptr().addr() % align, 0);
+            bytes.shrink_buffer().unwrap();
+            assert_eq!(bytes.buf.as_ptr().addr() % align, 0);
+            assert_eq!(bytes.collect::<Vec<u8>>(), base);
+        }
+
+        let err = BufBytes::with_aligned_capacity(&base[..], 16, 3).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    // 消費した順に記録し、覗いただけのバイトは記録しない
+    #[test]
+    fn with_recording_test() {
+        let base: Vec<u8> = (0..30).collect();
+        let mut bytes = BufBytes::with_recording(&base[..], 4).unwrap();
+
+        bytes.by_ref().take(3).for_each(drop);
+        bytes.peek_n(10).unwrap();
+        assert_eq!(bytes.recorded(), [0, 1, 2]);
+        bytes.consume(5);
+        bytes.by_ref().for_each(drop);
+        assert_eq!(bytes.recorded(), base);
+
+        assert!(BufBytes::new(&base[..]).unwrap().recorded().is_empty());
+    }
+
+    // into_innerで取り出したbaseは、読んだところから続く
+    #[test]
+    fn into_inner_test() {
+        let base = b"secret-key";
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+        bytes.by_ref().take(2).for_each(drop);
+        // 4byteバッファに読み込んだ分は捨てられる
+        assert_eq!(bytes.into_inner(), b"et-key");
+    }
+
+    // バッファより少なくしか返さないReaderでは、1.0未満になる
+    #[test]
+    fn fill_ratio_test() {
+        let bytes = BufBytes::with_capacity(ChunkFile { chunk: 3 }, 12).unwrap();
+        assert_eq!(bytes.fill_ratio(), 0.25);
+
+        let mut bytes = BufBytes::with_capacity(&[0; 10][..], 4).unwrap();
+        assert_eq!(bytes.fill_ratio(), 1.0);
+        bytes.by_ref().for_each(drop);
+        // 最後は2byteだけ読めた
+        assert_eq!(bytes.fill_ratio(), 0.5);
+    }
+
+    // エラーが起きる直前まで消費したオフセットが残る
+    #[test]
+    fn last_good_position_test() {
+        let mut bytes = BufBytes::with_capacity(ErrorFile::new(10), 4).unwrap();
+        assert_eq!(bytes.last_good_position(), 0);
+        assert_eq!(bytes.by_ref().count(), 8);
+        assert!(bytes.get_err().is_some());
+        assert_eq!(bytes.last_good_position(), 8);
+        // エラーの後に呼んでも変わらない
+        assert_eq!(bytes.next(), None);
+        assert_eq!(bytes.last_good_position(), 8);
+    }
+
+    // unreadで戻して読み直しても、last_good_positionは戻らない
+    #[test]
+    fn last_good_position_unread_test() {
+        let data: Vec<u8> = (0..20).collect();
+        let mut bytes = BufBytes::with_capacity(&data[..], 8).unwrap();
+        bytes.by_ref().take(5).for_each(drop);
+        assert_eq!(bytes.last_good_position(), 5);
+        bytes.unread(&[1, 2, 3]).unwrap();
+        assert_eq!(bytes.last_good_position(), 5);
+        bytes.next();
+        assert_eq!(bytes.last_good_position(), 5);
+        bytes.by_ref().take(3).for_each(drop);
+        assert_eq!(bytes.last_good_position(), 6);
+    }
+
+    // 違う型のbaseでも、同じ型として扱える
+    #[test]
+    fn boxed_test() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"file").unwrap();
+
+        let sources: Vec<BoxedBufBytes> = vec![
+            BufBytes::boxed(&b"slice"[..]).unwrap(),
+            BufBytes::boxed(std::io::Cursor::new(b"cursor".to_vec())).unwrap(),
+            BufBytes::boxed(file.reopen().unwrap()).unwrap(),
+        ];
+        let res: Vec<Vec<u8>> = sources.into_iter().map(|bytes| bytes.collect()).collect();
+        assert_eq!(res, [b"slice".to_vec(), b"cursor".to_vec(), b"file".to_vec()]);
+    }
+
+    // 作成は成功し、使ったときにエラーがわかる
+    #[test]
+    fn with_capacity_deferred_test() {
+        let bases: [Box<dyn Read>; 3] = [Box::new(&b"ok"[..]), Box::new(ErrorFile::new(0)), Box::new(&b""[..])];
+        let mut sources = bases.map(|base| BufBytes::with_capacity_deferred(base, 4));
+        assert_eq!(sources[0].by_ref().collect::<Vec<u8>>(), b"ok");
+        assert!(sources[0].get_err().is_none());
+
+        assert_eq!(sources[1].next(), None);
+        assert_eq!(sources[1].get_err().as_ref().unwrap().kind(), std::io::ErrorKind::Other);
+        assert_eq!(sources[2].next(), None);
+        assert_eq!(sources[2].get_err().as_ref().unwrap().kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    // たくさん読んでも容量を超えず、直近のバイトが残る
+    #[test]
+    fn with_ring_test() {
+        let base: Vec<u8> = (0..10000u32).map(|i| (i % 251) as u8).collect();
+        let mut bytes = BufBytes::with_ring(&base[..], 64, 100).unwrap();
+        assert!(bytes.ring_contents().is_empty());
+
+        bytes.by_ref().take(30).for_each(drop);
+        assert_eq!(bytes.ring_contents(), &base[..30]);
+        bytes.peek_n(200).unwrap();
+        bytes.discard_peeked();
+        assert_eq!(bytes.ring_contents(), &base[130..230]);
+        bytes.by_ref().for_each(drop);
+        assert_eq!(bytes.ring_contents(), &base[9900..]);
+    }
+
     #[test]
     fn try_block_success_test() {
         let base_txt = "abcdefg\nhijklmn\nopqrstu\nvwxyz00\n";
@@ -0,0 +1,93 @@
+//! 読み込みのメトリクスをprometheusで公開するメソッドたち(`prometheus` feature)
+
+use std::io::{Error, Read, Result};
+
+use prometheus::{Gauge, IntCounter, Registry};
+
+use crate::BufBytes;
+
+/// register_metricsで登録したメトリクス
+#[derive(Debug)]
+pub(crate) struct Metrics {
+    read_bytes: IntCounter,
+    refills: IntCounter,
+    utilization: Gauge,
+}
+
+impl Metrics {
+    /// baseから読み込んだバイト数を足す
+    pub(crate) fn on_read(&self, len: usize) {
+        self.read_bytes.inc_by(len as u64);
+    }
+
+    /// 再読み込みの回数と、そのときのバッファの埋まり具合を記録する
+    pub(crate) fn on_refill(&self, len: usize, capacity: usize) {
+        self.refills.inc();
+        self.utilization.set(len as f64 / capacity.max(1) as f64);
+    }
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 読み込みのメトリクスをregistryに登録する
+    /// 
+    /// 次のメトリクスを登録し、以降の読み込みで更新します。
+    /// - `bufbytes_read_bytes_total`: baseから読み込んだバイト数(Counter)
+    /// - `bufbytes_refills_total`: バッファを再読み込みした回数(Counter)
+    /// - `bufbytes_buffer_utilization`: 最後の再読み込みで、バッファが埋まった割合(Gauge, 0.0〜1.0)
+    /// 
+    /// 登録より前に読み込んだ分は含みません。  
+    /// 同じ名前のメトリクスがすでにregistryにあるなどで登録できなければ、エラーを返します。
+    pub fn register_metrics(&mut self, registry: &Registry) -> Result<()> {
+        let read_bytes = IntCounter::new("bufbytes_read_bytes_total", "Bytes read from the base reader").map_err(Error::other)?;
+        let refills = IntCounter::new("bufbytes_refills_total", "Number of buffer refills").map_err(Error::other)?;
+        let utilization = Gauge::new("bufbytes_buffer_utilization", "Fraction of the buffer filled by the last refill").map_err(Error::other)?;
+        registry.register(Box::new(read_bytes.clone())).map_err(Error::other)?;
+        registry.register(Box::new(refills.clone())).map_err(Error::other)?;
+        registry.register(Box::new(utilization.clone())).map_err(Error::other)?;
+        self.metrics = Some(Metrics { read_bytes, refills, utilization });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus::Registry;
+
+    use crate::BufBytes;
+
+    fn value(registry: &Registry, name: &str) -> f64 {
+        let family = registry.gather().into_iter().find(|f| f.name() == name).unwrap();
+        let metric = &family.get_metric()[0];
+        match name {
+            "bufbytes_buffer_utilization" => metric.get_gauge().get_value(),
+            _ => metric.get_counter().get_value(),
+        }
+    }
+
+    // 読み進めると値が増える
+    #[test]
+    fn register_metrics_test() {
+        let base = [0; 20];
+        let registry = Registry::new();
+        let mut bytes = BufBytes::with_capacity(&base[..], 8).unwrap();
+        bytes.register_metrics(&registry).unwrap();
+        assert_eq!(value(&registry, "bufbytes_read_bytes_total"), 0.0);
+
+        bytes.by_ref().take(9).for_each(drop);
+        assert_eq!(value(&registry, "bufbytes_read_bytes_total"), 8.0);
+        assert_eq!(value(&registry, "bufbytes_refills_total"), 1.0);
+        assert_eq!(value(&registry, "bufbytes_buffer_utilization"), 1.0);
+
+        bytes.by_ref().for_each(drop);
+        assert_eq!(value(&registry, "bufbytes_read_bytes_total"), 12.0);
+        assert_eq!(value(&registry, "bufbytes_refills_total"), 2.0);
+        assert_eq!(value(&registry, "bufbytes_buffer_utilization"), 0.5);
+
+        // 同じregistryに2回は登録できない
+        let mut other = BufBytes::new(&base[..]).unwrap();
+        assert!(other.register_metrics(&registry).is_err());
+    }
+}
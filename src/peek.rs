@@ -0,0 +1,279 @@
+//! バイトを消費せずに先読みするメソッドたち
+
+use std::io::{Read, Result};
+
+use crate::BufBytes;
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 次のバイトを消費せずに先読みする
+    /// 
+    /// バッファが空なら再読み込みします。EOFかio処理のエラーなら`None`です。
+    pub fn peek(&mut self) -> Option<u8> {
+        if self.buffer().is_empty() && !self.refill_buffer() {
+            return None;
+        }
+        self.peek_end = Self::position(self) + 1;
+        self.buffer().first().copied()
+    }
+
+    /// nバイトを消費せずに先読みする
+    /// 
+    /// 足りなければバッファを読み足します。(nがバッファより大きければ、バッファを広げます)  
+    /// EOFで足りなかったときは、残っている分だけのスライスを返します。  
+    /// io処理でエラーが起きて足りなかったときは、エラーを返します。
+    pub fn peek_n(&mut self, n: usize) -> Result<&[u8]> {
+        self.fill_at_least(n);
+        let buf = self.buffer();
+        if buf.len() < n && self.error.is_some() {
+            return Err(self.short_read_error());
+        }
+        let len = buf.len().min(n);
+        self.peek_end = Self::position(self) + len as u64;
+        Ok(&self.buffer()[..len])
+    }
+
+    /// 最後に[`peek`](Self::peek)や[`peek_n`](Self::peek_n)で覗いたバイトを、まとめて消費する
+    /// 
+    /// 先読みしたけれど使わなかったバイトを読み飛ばすのに使います。  
+    /// 覗いた後に`next()`などで一部を読んでいたら、覗いた範囲の残りだけを消費します。  
+    /// 何も覗いていなければ何もしません。
+    pub fn discard_peeked(&mut self) {
+        let n = self.peek_end.saturating_sub(Self::position(self));
+        self.consume(n as usize);
+        self.peek_end = 0;
+    }
+
+    /// 少なくともnバイト連続した、未消費のバッファを返す
+    /// 
+    /// `BufRead::fill_buf`と違い、nバイト以上あることを保証します。  
+    /// 返したスライスは消費しないので、使った分は[`consume`](Self::consume)で読み進めてください。  
+    /// nバイトに満たずにEOFになったら`UnexpectedEof`を、  
+    /// io処理でエラーが起きたらそのエラーを返します。(どちらもバイトは消費しません)
+    pub fn fill_buf_at_least(&mut self, n: usize) -> Result<&[u8]> {
+        self.fill_at_least(n);
+        if self.buffer().len() < n {
+            return Err(self.short_read_error());
+        }
+        Ok(self.buffer())
+    }
+
+    /// ストリームがprefixで始まるかを、消費せずに調べる
+    /// 
+    /// 残りがprefixより短ければ`false`です。
+    pub fn starts_with(&mut self, prefix: &[u8]) -> Result<bool> {
+        Ok(self.peek_n(prefix.len())? == prefix)
+    }
+
+    /// ストリームの先頭が、ワイルドカードのpatternに一致するかを、消費せずに調べる
+    /// 
+    /// `*`は0バイト以上の任意のバイト列に、`?`は任意の1バイトに一致します。 ほかのバイトはそのバイトにだけ一致します。  
+    /// patternに一致する先頭部分があれば`true`です。(残り全体が一致する必要はありません)  
+    /// `*`は一致するところが見つかるまで先読みするので、バッファが大きく広がることがあります。  
+    /// io処理でエラーが起きたら、そのエラーを返します。
+    pub fn match_glob(&mut self, pattern: &[u8]) -> Result<bool> {
+        Ok(self.glob_prefix_len(pattern)?.is_some())
+    }
+
+    /// [`match_glob`](Self::match_glob)と同じく調べ、一致したらその部分を消費する
+    /// 
+    /// `*`はできるだけ短く一致させるので、消費するのは一致するいちばん短い先頭部分です。  
+    /// 一致しなかったときは、何も消費しません。
+    pub fn consume_glob(&mut self, pattern: &[u8]) -> Result<bool> {
+        let Some(len) = self.glob_prefix_len(pattern)? else {
+            return Ok(false);
+        };
+        self.consume(len);
+        Ok(true)
+    }
+
+    /// patternに一致する、いちばん短い先頭部分の長さを求める
+    fn glob_prefix_len(&mut self, pattern: &[u8]) -> Result<Option<usize>> {
+        let (mut p, mut s) = (0, 0);
+        // 最後に通った`*`の位置と、そこで読み飛ばし始めたストリームの位置
+        let mut star: Option<(usize, usize)> = None;
+        while p < pattern.len() {
+            if pattern[p] == b'*' {
+                star = Some((p, s));
+                p += 1;
+                continue;
+            }
+            if self.buffer().len() <= s {
+                // 1バイトずつ広げると詰め直しが重いので、倍々に読み足す
+                self.fill_at_least((s + 1).max(self.buffer().len() * 2));
+                if self.buffer().len() <= s && self.error.is_some() {
+                    return Err(self.short_read_error());
+                }
+            }
+            let matched = self.buffer().get(s).is_some_and(|&b| pattern[p] == b'?' || pattern[p] == b);
+            if matched {
+                p += 1;
+                s += 1;
+                continue;
+            }
+            // 一致しなければ、最後の`*`に1バイト多く食べさせてやり直す
+            match star {
+                Some((star_p, star_s)) if star_s < self.buffer().len() => {
+                    star = Some((star_p, star_s + 1));
+                    p = star_p + 1;
+                    s = star_s + 1;
+                },
+                _ => return Ok(None),
+            }
+        }
+        Ok(Some(s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{ErrorKind, Read, Result};
+
+    use crate::BufBytes;
+
+    // 1回のreadで最大chunkバイトしか返さない仮想ファイル
+    struct ChunkReader<'a> {
+        data: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for ChunkReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let n = self.chunk.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    // peekしても消費しない
+    #[test]
+    fn peek_test() {
+        let base = b"abcd";
+        let mut bytes = BufBytes::with_capacity(&base[..], 2).unwrap();
+
+        assert_eq!(bytes.peek(), Some(b'a'));
+        assert_eq!(bytes.peek(), Some(b'a'));
+        bytes.by_ref().take(2).for_each(drop);
+        // バッファの境界では再読み込みする
+        assert_eq!(bytes.peek(), Some(b'c'));
+        assert_eq!(bytes.collect::<Vec<u8>>(), b"cd");
+    }
+
+    // 覗いた範囲の残りだけを消費する
+    #[test]
+    fn discard_peeked_test() {
+        let base = b"header:body";
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        assert_eq!(bytes.peek_n(7).unwrap(), b"header:");
+        assert_eq!(bytes.next(), Some(b'h'));
+        bytes.discard_peeked();
+        assert_eq!(bytes.next(), Some(b'b'));
+        // 2回目は何もしない
+        bytes.discard_peeked();
+        assert_eq!(bytes.next(), Some(b'o'));
+
+        // Iterator::peekableの先読みとも食い違わない
+        assert_eq!(bytes.peek(), Some(b'd'));
+        bytes.discard_peeked();
+        let mut peekable = bytes.peekable();
+        assert_eq!(peekable.peek(), Some(&b'y'));
+        assert_eq!(peekable.next(), Some(b'y'));
+        assert_eq!(peekable.next(), None);
+    }
+
+    #[test]
+    fn match_glob_test() {
+        let mut bytes = BufBytes::with_capacity(&b"GET /index.html HTTP/1.1\r\n"[..], 4).unwrap();
+        assert!(bytes.match_glob(b"GET /*.html").unwrap());
+        assert!(bytes.match_glob(b"G?T").unwrap());
+        assert!(bytes.match_glob(b"*").unwrap());
+        assert!(!bytes.match_glob(b"POST*").unwrap());
+        assert!(!bytes.match_glob(b"GET *.png").unwrap());
+        // 消費していない
+        assert_eq!(bytes.next(), Some(b'G'));
+    }
+
+    // `*`が一度長く食べすぎても、戻ってやり直せる
+    #[test]
+    fn match_glob_backtrack_test() {
+        let mut bytes = BufBytes::with_capacity(&b"abababc-tail"[..], 2).unwrap();
+        assert!(bytes.match_glob(b"a*abc").unwrap());
+        assert!(bytes.match_glob(b"*b?-").unwrap());
+        assert!(!bytes.match_glob(b"a*abd").unwrap());
+        // 一致したいちばん短い部分だけ消費する
+        assert!(bytes.consume_glob(b"*b").unwrap());
+        assert_eq!(bytes.by_ref().collect::<Vec<u8>>(), b"ababc-tail");
+    }
+
+    // 一致する。 バッファ境界をまたいでいても良い
+    #[test]
+    fn starts_with_match_test() {
+        let base = b"GET /index.html";
+        let mut bytes = BufBytes::with_capacity(&base[..], 3).unwrap();
+
+        assert!(bytes.starts_with(b"GET ").unwrap());
+        // 消費していない
+        assert_eq!(bytes.collect::<Vec<u8>>(), base);
+    }
+
+    #[test]
+    fn starts_with_mismatch_test() {
+        let base = b"POST /form";
+        let mut bytes = BufBytes::with_capacity(&base[..], 3).unwrap();
+
+        assert!(!bytes.starts_with(b"GET ").unwrap());
+        assert_eq!(bytes.next(), Some(b'P'));
+    }
+
+    // 残りがprefixより短いときはfalse
+    #[test]
+    fn starts_with_short_test() {
+        let base = b"GE";
+        let mut bytes = BufBytes::with_capacity(&base[..], 8).unwrap();
+
+        assert!(!bytes.starts_with(b"GET ").unwrap());
+        assert_eq!(bytes.collect::<Vec<u8>>(), b"GE");
+    }
+
+    // 途中まで読んだ位置からのpeekでも、バッファより長いpeekでも並びが崩れない
+    #[test]
+    fn peek_n_test() {
+        let base = b"0123456789";
+        let mut bytes = BufBytes::with_eager_error(&base[..], 4).unwrap();
+
+        bytes.by_ref().take(3).for_each(drop);
+        assert_eq!(bytes.peek_n(6).unwrap(), b"345678");
+        assert_eq!(bytes.position(), 3);
+        assert_eq!(bytes.collect::<Vec<u8>>(), b"3456789");
+    }
+
+    // 何回もrefillしないとnバイトにならないケース
+    #[test]
+    fn fill_buf_at_least_test() {
+        let base = ChunkReader { data: b"0123456789abcdef", chunk: 3 };
+        let mut bytes = BufBytes::with_capacity(base, 4).unwrap();
+        bytes.next();
+
+        let buf = bytes.fill_buf_at_least(10).unwrap();
+        assert!(buf.len() >= 10);
+        assert_eq!(&buf[..10], b"123456789a");
+
+        bytes.consume(10);
+        assert_eq!(bytes.collect::<Vec<u8>>(), b"bcdef");
+    }
+
+    // 足りないときはUnexpectedEofで、消費しない
+    #[test]
+    fn fill_buf_at_least_eof_test() {
+        let base = ChunkReader { data: b"01234", chunk: 2 };
+        let mut bytes = BufBytes::with_capacity(base, 4).unwrap();
+
+        let err = bytes.fill_buf_at_least(6).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(bytes.collect::<Vec<u8>>(), b"01234");
+    }
+}
@@ -0,0 +1,143 @@
+//! baseの`read`にかかった時間を計測するメソッドたち
+
+use std::{io::{Read, Result}, time::Duration};
+
+use crate::BufBytes;
+
+/// baseの`read`1回あたりの所要時間の分布
+/// 
+/// [`BufBytes::latency_histogram`]で作成します。  
+/// 計測していないときや、まだ1回も読み込んでいないときは、すべて0になります。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LatencyStats {
+    count: usize,
+    min: Duration,
+    max: Duration,
+    mean: Duration,
+    p50: Duration,
+    p99: Duration,
+}
+
+impl LatencyStats {
+    /// 計測した`read`の回数
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// いちばん速かった`read`の時間
+    pub fn min(&self) -> Duration {
+        self.min
+    }
+
+    /// いちばん遅かった`read`の時間
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// `read`の平均の時間
+    pub fn mean(&self) -> Duration {
+        self.mean
+    }
+
+    /// `read`の時間の中央値
+    pub fn p50(&self) -> Duration {
+        self.p50
+    }
+
+    /// `read`の時間の99パーセンタイル
+    pub fn p99(&self) -> Duration {
+        self.p99
+    }
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// baseの`read`にかかった時間を計測するBufBytesを作成
+    /// 
+    /// 再試行やEOF、エラーになった呼び出しも含めて、`read`1回ごとに時間を記録します。  
+    /// 分布は[`latency_histogram`](Self::latency_histogram)で取れます。 遅いioの原因調査に使えます。  
+    /// 記録は読み込みの回数だけ増えるので、長いストリームでは大きなバッファと組み合わせてください。
+    pub fn with_profiling(base: B, size: usize) -> Result<Self> {
+        let mut bytes = Self::unfilled(base, vec![0; size]);
+        bytes.latencies = Some(Vec::new());
+        bytes.first_fill()
+    }
+
+    /// これまでのbaseの`read`の所要時間の分布
+    /// 
+    /// [`with_profiling`](Self::with_profiling)で作ったときだけ計測しています。  
+    /// パーセンタイルは、小さい方から数えた順位(nearest-rank)で求めます。
+    pub fn latency_histogram(&self) -> LatencyStats {
+        let Some(latencies) = self.latencies.as_ref().filter(|l| !l.is_empty()) else {
+            return LatencyStats::default();
+        };
+        let mut sorted = latencies.clone();
+        sorted.sort_unstable();
+        let count = sorted.len();
+        let rank = |p: usize| sorted[(count * p).div_ceil(100).max(1) - 1];
+        LatencyStats {
+            count,
+            min: sorted[0],
+            max: sorted[count - 1],
+            mean: sorted.iter().sum::<Duration>() / count as u32,
+            p50: rank(50),
+            p99: rank(99),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Read, thread, time::Duration};
+
+    use crate::BufBytes;
+
+    // readごとにdelaysの時間だけ待ってから読める、remainingバイトの仮想ファイル
+    struct SlowFile {
+        delays: Vec<Duration>,
+        reads: usize,
+        remaining: usize,
+    }
+
+    impl Read for SlowFile {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            thread::sleep(self.delays[self.reads % self.delays.len()]);
+            self.reads += 1;
+            let n = self.remaining.min(buf.len());
+            buf[..n].fill(0);
+            self.remaining -= n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn latency_histogram_test() {
+        let ms = Duration::from_millis;
+        // 10回に1回だけ遅い
+        let mut delays = vec![ms(1); 9];
+        delays.push(ms(30));
+        let base = SlowFile { delays, reads: 0, remaining: 190 };
+        let mut bytes = BufBytes::with_profiling(base, 10).unwrap();
+        assert_eq!(bytes.by_ref().count(), 190);
+
+        // 19回読んで、最後にEOFの1回
+        let stats = bytes.latency_histogram();
+        assert_eq!(stats.count(), 20);
+        assert!(stats.min() >= ms(1));
+        assert!(stats.p50() >= ms(1) && stats.p50() < ms(30));
+        assert!(stats.p99() >= ms(30));
+        assert_eq!(stats.max(), stats.p99());
+        assert!(stats.mean() > stats.min() && stats.mean() < stats.max());
+    }
+
+    // 計測していないときは空
+    #[test]
+    fn latency_histogram_disabled_test() {
+        let mut bytes = BufBytes::with_capacity(&[1, 2, 3][..], 2).unwrap();
+        bytes.by_ref().for_each(drop);
+        assert_eq!(bytes.latency_histogram().count(), 0);
+        assert_eq!(bytes.latency_histogram().max(), Duration::ZERO);
+    }
+}
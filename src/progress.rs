@@ -0,0 +1,71 @@
+//! 読み込みの進捗を公開する
+
+use std::{io::Read, sync::{Arc, atomic::{AtomicU64, Ordering}}};
+
+use crate::BufBytes;
+
+/// 読み込みの進捗を覗くためのハンドル
+/// 
+/// [`BufBytes::progress_handle`]で作成します。  
+/// 中身はアトミックなカウンタなので、別スレッドからロックなしで読めます。
+#[derive(Debug, Clone)]
+pub struct ProgressHandle {
+    bytes_read: Arc<AtomicU64>,
+}
+
+impl ProgressHandle {
+    /// baseから読み込んだ総バイト数
+    /// 
+    /// バッファへ読み込むたびに更新されるので、まだ返していないバッファ分も含みます。
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read.load(Ordering::Relaxed)
+    }
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 読み込みの進捗を覗くハンドルを作る
+    pub fn progress_handle(&self) -> ProgressHandle {
+        ProgressHandle { bytes_read: Arc::clone(&self.progress) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::BufBytes;
+
+    #[test]
+    fn progress_handle_test() {
+        let base = vec![0u8; 100];
+        let mut bytes = BufBytes::with_capacity(std::io::Cursor::new(base), 16).unwrap();
+        let handle = bytes.progress_handle();
+
+        // 作成時に1回読み込んでいる
+        assert_eq!(handle.bytes_read(), 16);
+        bytes.by_ref().take(17).for_each(drop);
+        assert_eq!(handle.bytes_read(), 32);
+    }
+
+    // 本体とハンドルが別スレッドにあっても値を共有できる
+    #[test]
+    fn progress_handle_thread_test() {
+        let base = vec![0u8; 10000];
+        let bytes = BufBytes::with_capacity(std::io::Cursor::new(base), 64).unwrap();
+        let handle = bytes.progress_handle();
+
+        let reader = thread::spawn(move || bytes.count());
+        // 読んでいる途中に覗いても、値は減らない
+        let mut last = 0;
+        while !reader.is_finished() {
+            let now = handle.bytes_read();
+            assert!(last <= now);
+            last = now;
+        }
+        assert_eq!(reader.join().unwrap(), 10000);
+        assert_eq!(handle.bytes_read(), 10000);
+    }
+}
@@ -0,0 +1,379 @@
+//! 決まったバイト数をまとめて読み込むメソッドたち
+
+use std::{collections::VecDeque, io::{Error, ErrorKind, Read, Result}};
+
+use crate::BufBytes;
+
+// 符号なし整数をNバイト読み込むメソッドを作る
+macro_rules! read_uint {
+    ($($name:ident: $ty:ty, $from:ident, $doc:literal;)*) => {
+        $(
+            #[doc = $doc]
+            /// 
+            /// 足りずにEOFになったら`UnexpectedEof`を返します。
+            pub fn $name(&mut self) -> Result<$ty> {
+                Ok(<$ty>::$from(self.read_array()?))
+            }
+        )*
+    };
+}
+
+// 符号なし版で読んで、別の型にビットキャストするメソッドを作る
+macro_rules! read_cast {
+    ($($name:ident: $ty:ty = $read:ident($cast:expr), $doc:literal;)*) => {
+        $(
+            #[doc = $doc]
+            /// 
+            /// 足りずにEOFになったら`UnexpectedEof`を返します。
+            pub fn $name(&mut self) -> Result<$ty> {
+                Ok($cast(self.$read()?))
+            }
+        )*
+    };
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// outを埋めるまで読み込み、読み込めたバイト数を返す
+    /// 
+    /// バッファが足りなければ再読み込みします。  
+    /// EOFかエラーで途中までしか読めなかったときは、outの長さより小さい値になります。
+    pub(crate) fn fill_slice(&mut self, out: &mut [u8]) -> usize {
+        let mut filled = 0;
+        while filled < out.len() {
+            if self.buffer().is_empty() && !self.refill_buffer() {
+                break;
+            }
+            let buf = self.buffer();
+            let n = buf.len().min(out.len() - filled);
+            out[filled..filled + n].copy_from_slice(&buf[..n]);
+            self.consume(n);
+            filled += n;
+        }
+        filled
+    }
+
+    /// outの長さまで読み込み、読み込めたバイト数を返す
+    /// 
+    /// ヒープを使わずに、スタックの配列などへ少しずつ読むのに使います。  
+    /// `Read::read`と似ていますが、足りなければ何度でも再読み込みしてoutを埋めます。  
+    /// 残りがoutより少なければ、読めた分だけを返します。(EOFなら0)  
+    /// 1バイトも読めずにio処理のエラーが起きたときだけ、そのエラーを返します。
+    pub fn read_to_slice(&mut self, out: &mut [u8]) -> Result<usize> {
+        let filled = self.fill_slice(out);
+        if filled == 0 && !out.is_empty() && self.error.is_some() {
+            return Err(self.short_read_error());
+        }
+        Ok(filled)
+    }
+
+    /// Nバイト読み込んで配列で返す
+    /// 
+    /// バッファの境界やバッファより大きいNも気にせず読めます。  
+    /// Nバイトに満たずにEOFになったら`UnexpectedEof`を、  
+    /// io処理でエラーが起きたらそのエラーを返します。(読めた分のバイトは消費されます)
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut out = [0; N];
+        if self.fill_slice(&mut out) < N {
+            return Err(self.short_read_error());
+        }
+        Ok(out)
+    }
+
+    read_uint! {
+        read_u16_le: u16, from_le_bytes, "リトルエンディアンの`u16`を読み込む";
+        read_u16_be: u16, from_be_bytes, "ビッグエンディアンの`u16`を読み込む";
+        read_u32_le: u32, from_le_bytes, "リトルエンディアンの`u32`を読み込む";
+        read_u32_be: u32, from_be_bytes, "ビッグエンディアンの`u32`を読み込む";
+        read_u64_le: u64, from_le_bytes, "リトルエンディアンの`u64`を読み込む";
+        read_u64_be: u64, from_be_bytes, "ビッグエンディアンの`u64`を読み込む";
+    }
+
+    read_cast! {
+        read_i16_le: i16 = read_u16_le(|v| v as i16), "リトルエンディアンの`i16`を読み込む";
+        read_i16_be: i16 = read_u16_be(|v| v as i16), "ビッグエンディアンの`i16`を読み込む";
+        read_i32_le: i32 = read_u32_le(|v| v as i32), "リトルエンディアンの`i32`を読み込む";
+        read_i32_be: i32 = read_u32_be(|v| v as i32), "ビッグエンディアンの`i32`を読み込む";
+        read_i64_le: i64 = read_u64_le(|v| v as i64), "リトルエンディアンの`i64`を読み込む";
+        read_i64_be: i64 = read_u64_be(|v| v as i64), "ビッグエンディアンの`i64`を読み込む";
+        read_f32_le: f32 = read_u32_le(f32::from_bits), "リトルエンディアンの`f32`を読み込む";
+        read_f32_be: f32 = read_u32_be(f32::from_bits), "ビッグエンディアンの`f32`を読み込む";
+        read_f64_le: f64 = read_u64_le(f64::from_bits), "リトルエンディアンの`f64`を読み込む";
+        read_f64_be: f64 = read_u64_be(f64::from_bits), "ビッグエンディアンの`f64`を読み込む";
+    }
+
+    /// 読み取り位置がalignmentの倍数になるまで読み飛ばす
+    /// 
+    /// 飛ばしたバイト数を返します。すでに揃っていれば、0を返してすぐ終わります。  
+    /// 途中でEOFになったら`UnexpectedEof`を返します。  
+    /// alignmentが0のときは`InvalidInput`です。
+    pub fn align_to(&mut self, alignment: usize) -> Result<usize> {
+        if alignment == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "alignment must be non-zero"));
+        }
+        let rem = (Self::position(self) % alignment as u64) as usize;
+        let pad = (alignment - rem) % alignment;
+        if self.skip_bytes(pad as u64) < pad as u64 {
+            return Err(self.short_read_error());
+        }
+        Ok(pad)
+    }
+
+    /// ストリームを最後まで読んで、末尾のnバイトを返す
+    /// 
+    /// 全体をメモリに持たず、nバイトのリングバッファで末尾だけを覚えておきます。  
+    /// 残りがnバイト未満なら、あるだけを返します。  
+    /// io処理でエラーが起きたら、そのエラーを返します。
+    pub fn tail(&mut self, n: usize) -> Result<Vec<u8>> {
+        let mut ring = VecDeque::with_capacity(n);
+        loop {
+            if self.buffer().is_empty() && !self.refill_buffer() {
+                return match self.error {
+                    Some(_) => Err(self.short_read_error()),
+                    None => Ok(ring.into()),
+                };
+            }
+            let buf = self.buffer();
+            // 末尾nバイトに入らない分は、リングに入れる前に捨てる
+            let chunk = &buf[buf.len().saturating_sub(n)..];
+            let overflow = (ring.len() + chunk.len()).saturating_sub(n);
+            ring.drain(..overflow);
+            ring.extend(chunk);
+            self.consume(buf.len());
+        }
+    }
+
+    /// 残りを全部読み込んでoutに追記する。 ただし、maxバイトまで
+    /// 
+    /// 追記したバイト数を返します。  
+    /// maxバイト読んでもまだ続きがあるときは、そこで止めて`InvalidData`を返します。  
+    /// (outにはちょうどmaxバイトが追記されています)  
+    /// 信頼できない入力を、メモリを使いすぎずに読むのに使います。
+    pub fn read_to_end_limited(&mut self, out: &mut Vec<u8>, max: usize) -> Result<usize> {
+        let mut read = 0;
+        loop {
+            if self.buffer().is_empty() && !self.refill_buffer() {
+                return match self.error {
+                    Some(_) => Err(self.short_read_error()),
+                    None => Ok(read),
+                };
+            }
+            if read == max {
+                return Err(Error::new(ErrorKind::InvalidData, format!("stream exceeds {} bytes", max)));
+            }
+            let buf = self.buffer();
+            let n = buf.len().min(max - read);
+            out.extend_from_slice(&buf[..n]);
+            self.consume(n);
+            read += n;
+        }
+    }
+
+    /// 残りを全部読み込んで、UTF-8の文字列としてoutに追記する
+    /// 
+    /// 追記したバイト数を返します。  
+    /// 全部読んでからまとめてUTF-8か調べるので、不正なUTF-8なら`InvalidData`を返し、outは変えません。  
+    /// io処理でエラーが起きたときも、そのエラーを返してoutは変えません。  
+    /// (どちらのときも、読んだバイトは消費されます)
+    pub fn read_to_string(&mut self, out: &mut String) -> Result<usize> {
+        let mut buf = Vec::new();
+        self.pump(|chunk| {
+            buf.extend_from_slice(chunk);
+            Ok(())
+        })?;
+        let s = String::from_utf8(buf).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        out.push_str(&s);
+        Ok(s.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::BufBytes;
+
+    // バッファ境界をまたいで読める
+    #[test]
+    fn read_array_across_buffer_test() {
+        let base = b"MAGIC123rest";
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        assert_eq!(&bytes.read_array::<3>().unwrap(), b"MAG");
+        // 4byteバッファの境界をまたぐ
+        assert_eq!(&bytes.read_array::<2>().unwrap(), b"IC");
+        assert_eq!(bytes.next(), Some(b'1'));
+    }
+
+    // バッファより大きいNでも読める
+    #[test]
+    fn read_array_larger_than_buffer_test() {
+        let base = b"0123456789abcdef";
+        let mut bytes = BufBytes::with_capacity(&base[..], 3).unwrap();
+
+        assert_eq!(&bytes.read_array::<10>().unwrap(), b"0123456789");
+        assert_eq!(bytes.collect::<Vec<u8>>(), b"abcdef");
+    }
+
+    // outちょうど、何度も再読み込みをまたぐとき、残りが少ないとき
+    #[test]
+    fn read_to_slice_test() {
+        let base: Vec<u8> = (0..30).collect();
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        let mut out = [0; 4];
+        assert_eq!(bytes.read_to_slice(&mut out).unwrap(), 4);
+        assert_eq!(out, [0, 1, 2, 3]);
+
+        let mut out = [0; 16];
+        assert_eq!(bytes.read_to_slice(&mut out).unwrap(), 16);
+        assert_eq!(out[..], base[4..20]);
+
+        assert_eq!(bytes.read_to_slice(&mut out).unwrap(), 10);
+        assert_eq!(out[..10], base[20..]);
+        assert_eq!(bytes.read_to_slice(&mut out).unwrap(), 0);
+    }
+
+    // 足りなければUnexpectedEof
+    #[test]
+    fn read_array_eof_test() {
+        let base = b"short";
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        let err = bytes.read_array::<8>().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    // 既にある中身の後ろに追記する
+    #[test]
+    fn read_to_string_test() {
+        let base = "こんにちは, world";
+        let mut bytes = BufBytes::with_capacity(base.as_bytes(), 4).unwrap();
+        let mut out = String::from("> ");
+        assert_eq!(bytes.read_to_string(&mut out).unwrap(), base.len());
+        assert_eq!(out, "> こんにちは, world");
+    }
+
+    // 不正なUTF-8ならInvalidDataで、outは変わらない
+    #[test]
+    fn read_to_string_invalid_test() {
+        let base = b"valid \xff\xfe tail";
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+        let mut out = String::from("keep");
+        let err = bytes.read_to_string(&mut out).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(out, "keep");
+    }
+
+    // maxを超えるデータは、ちょうどmaxバイトで止まる
+    #[test]
+    fn read_to_end_limited_exceed_test() {
+        let base: Vec<u8> = (0..100).collect();
+        let mut bytes = BufBytes::with_capacity(&base[..], 16).unwrap();
+
+        let mut out = Vec::new();
+        let err = bytes.read_to_end_limited(&mut out, 40).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(out, &base[..40]);
+    }
+
+    // max以下のデータは全部読める
+    #[test]
+    fn read_to_end_limited_within_test() {
+        let base: Vec<u8> = (0..100).collect();
+        for max in [100, 101, 1000] {
+            let mut bytes = BufBytes::with_capacity(&base[..], 16).unwrap();
+            let mut out = Vec::new();
+            assert_eq!(bytes.read_to_end_limited(&mut out, max).unwrap(), 100);
+            assert_eq!(out, base);
+        }
+    }
+
+    // エンディアンごとに、境界をまたいでも読める
+    #[test]
+    fn read_uint_test() {
+        let base = [0x01, 0x02, 0x01, 0x02, 0x01, 0x02, 0x03, 0x04, 0x01, 0x02, 0x03, 0x04];
+        let mut bytes = BufBytes::with_capacity(&base[..], 3).unwrap();
+
+        assert_eq!(bytes.read_u16_le().unwrap(), 0x0201);
+        assert_eq!(bytes.read_u16_be().unwrap(), 0x0102);
+        assert_eq!(bytes.read_u32_le().unwrap(), 0x04030201);
+        assert_eq!(bytes.read_u32_be().unwrap(), 0x01020304);
+        assert_eq!(bytes.read_u64_le().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+
+    // 負の値が正しく復元される
+    #[test]
+    fn read_int_test() {
+        let mut base = vec![0xff, 0xff];
+        base.extend((-2i32).to_be_bytes());
+        base.extend(i64::MIN.to_le_bytes());
+        base.extend(1234i16.to_be_bytes());
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        assert_eq!(bytes.read_i16_le().unwrap(), -1);
+        assert_eq!(bytes.read_i32_be().unwrap(), -2);
+        assert_eq!(bytes.read_i64_le().unwrap(), i64::MIN);
+        assert_eq!(bytes.read_i16_be().unwrap(), 1234);
+    }
+
+    #[test]
+    fn read_float_test() {
+        let mut base = Vec::new();
+        base.extend((-1.5f32).to_le_bytes());
+        base.extend(std::f64::consts::PI.to_be_bytes());
+        let mut bytes = BufBytes::with_capacity(&base[..], 5).unwrap();
+
+        assert_eq!(bytes.read_f32_le().unwrap(), -1.5);
+        assert_eq!(bytes.read_f64_be().unwrap(), std::f64::consts::PI);
+    }
+
+    // 大きなストリームでも、末尾nバイトだけ覚えている
+    #[test]
+    fn tail_test() {
+        let base: Vec<u8> = (0..=255).cycle().take(1 << 20).collect();
+        let mut bytes = BufBytes::with_capacity(&base[..], 1000).unwrap();
+
+        let tail = bytes.tail(16).unwrap();
+        assert_eq!(tail, &base[base.len() - 16..]);
+        // リングの容量はnバイト程度
+        assert!(tail.capacity() < 64);
+        assert_eq!(bytes.next(), None);
+    }
+
+    // nバイト未満なら、あるだけ返す。 バッファより大きいnでも良い
+    #[test]
+    fn tail_short_test() {
+        let base: Vec<u8> = (0..10).collect();
+        let mut bytes = BufBytes::with_capacity(&base[..], 3).unwrap();
+        assert_eq!(bytes.tail(100).unwrap(), base);
+
+        let mut bytes = BufBytes::with_capacity(&base[..], 3).unwrap();
+        assert_eq!(bytes.tail(5).unwrap(), &base[5..]);
+    }
+
+    // 色々なオフセットとalignmentの組み合わせ
+    #[test]
+    fn align_to_test() {
+        let base: Vec<u8> = (0..64).collect();
+        for (offset, alignment, pad) in [(0, 4, 0), (1, 4, 3), (3, 4, 1), (4, 4, 0), (5, 8, 3), (9, 16, 7), (7, 1, 0), (10, 3, 2)] {
+            let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+            bytes.by_ref().take(offset).for_each(drop);
+
+            assert_eq!(bytes.align_to(alignment).unwrap(), pad, "offset = {}, alignment = {}", offset, alignment);
+            assert_eq!(bytes.next(), Some((offset + pad) as u8));
+        }
+    }
+
+    #[test]
+    fn align_to_error_test() {
+        let base: Vec<u8> = (0..6).collect();
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+        assert_eq!(bytes.align_to(0).unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        bytes.next();
+        assert_eq!(bytes.align_to(8).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+}
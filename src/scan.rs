@@ -0,0 +1,458 @@
+//! バイトを順に調べながら消費するメソッドたち
+
+use std::{io::{Read, Result}, ops::ControlFlow};
+
+use crate::BufBytes;
+
+// スライスの中を探す処理。 memchr featureがあればmemchrを使う
+
+#[cfg(feature = "memchr")]
+fn position_of(byte: u8, buf: &[u8]) -> Option<usize> {
+    memchr::memchr(byte, buf)
+}
+
+#[cfg(not(feature = "memchr"))]
+fn position_of(byte: u8, buf: &[u8]) -> Option<usize> {
+    buf.iter().position(|&b| b == byte)
+}
+
+#[cfg(feature = "memchr")]
+fn count_of(byte: u8, buf: &[u8]) -> usize {
+    memchr::memchr_iter(byte, buf).count()
+}
+
+#[cfg(not(feature = "memchr"))]
+fn count_of(byte: u8, buf: &[u8]) -> usize {
+    buf.iter().filter(|&&b| b == byte).count()
+}
+
+#[cfg(feature = "memchr")]
+pub(crate) fn pattern_position(pattern: &[u8], buf: &[u8]) -> Option<usize> {
+    memchr::memmem::find(buf, pattern)
+}
+
+#[cfg(not(feature = "memchr"))]
+pub(crate) fn pattern_position(pattern: &[u8], buf: &[u8]) -> Option<usize> {
+    buf.windows(pattern.len()).position(|w| w == pattern)
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 残りのバイトのうち、byteがいくつあるかを数える
+    /// 
+    /// 大きなログの行数を数える(`count_byte(b'\n')`)のに使えます。  
+    /// バッファ単位でまとめて数えるので、`filter().count()`より速く、`memchr` featureでさらに速くなります。  
+    /// 最後まで消費します。 io処理でエラーが起きたら、そのエラーを返します。
+    pub fn count_byte(&mut self, byte: u8) -> Result<u64> {
+        let count = self.fold_chunks(0, |acc, buf| acc + count_of(byte, buf) as u64);
+        match self.error {
+            Some(_) => Err(self.short_read_error()),
+            None => Ok(count),
+        }
+    }
+
+    /// byteが来るところまで読み進め、そのオフセットを返す
+    /// 
+    /// オフセットは、このメソッドを呼んだ位置から数えたバイト数です。  
+    /// 見つけたbyteは消費しないので、次の`next()`でそのバイトが返ります。  
+    /// 見つからずにEOFになったら`None`を、io処理でエラーが起きたらそのエラーを返します。
+    pub fn find_byte(&mut self, byte: u8) -> Result<Option<u64>> {
+        let mut offset = 0;
+        loop {
+            if self.buffer().is_empty() && !self.refill_buffer() {
+                return match self.error {
+                    Some(_) => Err(self.short_read_error()),
+                    None => Ok(None),
+                };
+            }
+            let buf = self.buffer();
+            if let Some(i) = position_of(byte, buf) {
+                self.consume(i);
+                return Ok(Some(offset + i as u64));
+            }
+            offset += buf.len() as u64;
+            self.consume(buf.len());
+        }
+    }
+
+    /// patternが現れるところまで読み進め、その先頭のオフセットを返す
+    /// 
+    /// バッファの境界をまたぐpatternも見つけます。 (patternがバッファより長ければ、バッファを広げます)  
+    /// オフセットや消費のしかた、EOFやエラーのときは[`find_byte`](Self::find_byte)と同じです。  
+    /// 空のpatternは、すぐに`Some(0)`を返します。
+    pub fn find_pattern(&mut self, pattern: &[u8]) -> Result<Option<u64>> {
+        self.scan_pattern(pattern, |_| {})
+    }
+
+    /// patternが来るまで読み込んで、outに追記する
+    /// 
+    /// [`read_until`](Self::read_until)の複数バイト版です。 (例えば`b"\r\n\r\n"`まで)  
+    /// pattern自体もoutに入れ、消費します。 バッファの境界をまたぐpatternも見つけます。  
+    /// 見つかれば`true`を、見つからないままEOFになったら、そこまでを追記して`false`を返します。  
+    /// io処理でエラーが起きたら、そのエラーを返します。(読めた分はoutに入っています)
+    pub fn read_until_pattern(&mut self, pattern: &[u8], out: &mut Vec<u8>) -> Result<bool> {
+        if self.scan_pattern(pattern, |skipped| out.extend_from_slice(skipped))?.is_none() {
+            return Ok(false);
+        }
+        out.extend_from_slice(pattern);
+        self.consume(pattern.len());
+        Ok(true)
+    }
+
+    /// patternの先頭まで読み進め、そのオフセットを返す
+    /// 
+    /// 読み飛ばしたバイトは、消費する前にon_skipへ渡す
+    fn scan_pattern(&mut self, pattern: &[u8], mut on_skip: impl FnMut(&[u8])) -> Result<Option<u64>> {
+        if pattern.is_empty() {
+            return Ok(Some(0));
+        }
+        let mut offset = 0;
+        loop {
+            self.fill_at_least(pattern.len());
+            let buf = self.buffer();
+            if buf.len() < pattern.len() {
+                // もう見つかるだけのバイトがない
+                on_skip(buf);
+                self.consume(buf.len());
+                return match self.error {
+                    Some(_) => Err(self.short_read_error()),
+                    None => Ok(None),
+                };
+            }
+            if let Some(i) = pattern_position(pattern, buf) {
+                on_skip(&buf[..i]);
+                self.consume(i);
+                return Ok(Some(offset + i as u64));
+            }
+            // 境界をまたいで続くかもしれないので、末尾のpattern.len() - 1バイトは残す
+            let n = buf.len() - (pattern.len() - 1);
+            on_skip(&buf[..n]);
+            offset += n as u64;
+            self.consume(n);
+        }
+    }
+
+    /// delimが来るまで読み込んで、outに追記する
+    /// 
+    /// delim自体もoutに入れ、消費します。追記したバイト数を返します。  
+    /// delimが来ないままEOFになったときは、そこまでを追記します。(0ならEOF)  
+    /// io処理でエラーが起きたら、そのエラーを返します。(読めた分はoutに入っています)
+    pub fn read_until(&mut self, delim: u8, out: &mut Vec<u8>) -> Result<usize> {
+        Ok(self.read_until_by(out, |b| b == delim)?.0)
+    }
+
+    /// delimsのどれかが来るまで読み込んで、outに追記する
+    /// 
+    /// どのデリミタで止まったかを返します。(デリミタ自体もoutに入れ、消費します)  
+    /// どれも来ないままEOFになったときは、そこまでを追記して`None`を返します。  
+    /// io処理でエラーが起きたら、そのエラーを返します。(読めた分はoutに入っています)
+    pub fn read_until_any(&mut self, delims: &[u8], out: &mut Vec<u8>) -> Result<Option<u8>> {
+        Ok(self.read_until_by(out, |b| delims.contains(&b))?.1)
+    }
+
+    /// is_delimがtrueになるバイトまで読み込んで、outに追記する
+    /// 
+    /// 追記したバイト数と、止まったデリミタ(EOFならNone)を返す
+    fn read_until_by(&mut self, out: &mut Vec<u8>, is_delim: impl Fn(u8) -> bool) -> Result<(usize, Option<u8>)> {
+        let mut read = 0;
+        loop {
+            if self.buffer().is_empty() && !self.refill_buffer() {
+                return match self.error {
+                    Some(_) => Err(self.short_read_error()),
+                    None => Ok((read, None)),
+                };
+            }
+            let buf = self.buffer();
+            match buf.iter().position(|&b| is_delim(b)) {
+                Some(i) => {
+                    let delim = buf[i];
+                    out.extend_from_slice(&buf[..=i]);
+                    self.consume(i + 1);
+                    return Ok((read + i + 1, Some(delim)));
+                },
+                None => {
+                    out.extend_from_slice(buf);
+                    read += buf.len();
+                    self.consume(buf.len());
+                },
+            }
+        }
+    }
+
+    /// otherとバイトごとに比べて、最初に食い違うオフセットを返す
+    /// 
+    /// オフセットは、このメソッドを呼んだ位置から数えたバイト数です。  
+    /// 片方だけ先にEOFになったときは、そのオフセットを食い違いとして返します。  
+    /// 完全に一致すれば`None`です。  
+    /// 一致した部分だけを消費するので、食い違ったバイトはどちらも次の`next()`で読めます。
+    pub fn diff_offset<B2: Read>(&mut self, other: &mut BufBytes<B2>) -> Result<Option<u64>> {
+        let mut offset = 0;
+        loop {
+            let a_eof = self.buffer().is_empty() && !self.refill_buffer();
+            let b_eof = other.buffer().is_empty() && !other.refill_buffer();
+            if self.error.is_some() {
+                return Err(self.short_read_error());
+            }
+            if other.error.is_some() {
+                return Err(other.short_read_error());
+            }
+            match (a_eof, b_eof) {
+                (true, true) => return Ok(None),
+                (true, false) | (false, true) => return Ok(Some(offset)),
+                (false, false) => {},
+            }
+            let (a, b) = (self.buffer(), other.buffer());
+            let len = a.len().min(b.len());
+            if let Some(i) = a[..len].iter().zip(&b[..len]).position(|(a, b)| a != b) {
+                self.consume(i);
+                other.consume(i);
+                return Ok(Some(offset + i as u64));
+            }
+            self.consume(len);
+            other.consume(len);
+            offset += len as u64;
+        }
+    }
+
+    /// 各バイトをstepに渡して、ステートマシンを動かす
+    /// 
+    /// stepが`ControlFlow::Break`を返したら止まります。  
+    /// このとき、Breakを返したバイトは消費しないので、次の`next()`でそのバイトから読めます。  
+    /// EOFまで読み切ったら`Ok(())`を、io処理でエラーが起きたらそのエラーを返します。
+    pub fn drive<S>(&mut self, state: &mut S, mut step: impl FnMut(&mut S, u8) -> ControlFlow<()>) -> Result<()> {
+        loop {
+            if self.buffer().is_empty() && !self.refill_buffer() {
+                return match self.error {
+                    Some(_) => Err(self.short_read_error()),
+                    None => Ok(()),
+                };
+            }
+            let buf = self.buffer();
+            let stop = buf.iter().position(|&b| step(state, b).is_break());
+            match stop {
+                Some(i) => {
+                    self.consume(i);
+                    return Ok(());
+                },
+                None => self.consume(buf.len()),
+            }
+        }
+    }
+
+    /// 読み込んだチャンクを、順にfに渡す
+    /// 
+    /// 未消費のバイトが残っていれば、まずそれを渡します。  
+    /// fが返したエラーはそのまま返して止まります。(そのチャンクは消費しないので、続きから読み直せます)  
+    /// EOFまで読み切ったら`Ok(())`を、io処理でエラーが起きたらそのエラーを返します。
+    pub fn pump(&mut self, mut f: impl FnMut(&[u8]) -> Result<()>) -> Result<()> {
+        loop {
+            if self.buffer().is_empty() && !self.refill_buffer() {
+                return match self.error {
+                    Some(_) => Err(self.short_read_error()),
+                    None => Ok(()),
+                };
+            }
+            f(self.buffer())?;
+            self.consume(self.buffer().len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::ControlFlow;
+
+    use crate::BufBytes;
+
+    // 1byteずつ数えたものと一致する
+    #[test]
+    fn count_byte_test() {
+        let base: Vec<u8> = (0..10000u32).map(|i| if i % 7 == 0 { b'\n' } else { (i % 251) as u8 }).collect();
+        let naive = base.iter().filter(|&&b| b == b'\n').count() as u64;
+        let mut bytes = BufBytes::with_capacity(&base[..], 100).unwrap();
+        assert_eq!(bytes.count_byte(b'\n').unwrap(), naive);
+        assert_eq!(bytes.next(), None);
+    }
+
+    // 見つけたバイトは消費しない
+    #[test]
+    fn find_byte_test() {
+        let mut bytes = BufBytes::with_capacity(&b"key=value;next"[..], 3).unwrap();
+        assert_eq!(bytes.find_byte(b';').unwrap(), Some(9));
+        assert_eq!(bytes.next(), Some(b';'));
+        assert_eq!(bytes.find_byte(b'!').unwrap(), None);
+    }
+
+    // バッファ境界をまたぐパターンも見つかる
+    #[test]
+    fn find_pattern_test() {
+        let base = b"aaaaabab--END--tail";
+        for size in [1, 2, 3, 4, 7, 64] {
+            let mut bytes = BufBytes::with_capacity(&base[..], size).unwrap();
+            assert_eq!(bytes.find_pattern(b"--END--").unwrap(), Some(8));
+            assert_eq!(bytes.collect::<Vec<u8>>(), b"--END--tail");
+
+            let mut bytes = BufBytes::with_capacity(&base[..], size).unwrap();
+            assert_eq!(bytes.find_pattern(b"abab-").unwrap(), Some(4));
+            assert_eq!(bytes.find_pattern(b"missing").unwrap(), None);
+        }
+    }
+
+    // 全チャンクが順に渡り、つなげると元のデータになる
+    #[test]
+    fn pump_test() {
+        let base: Vec<u8> = (0..50).collect();
+        let mut bytes = BufBytes::with_capacity(&base[..], 8).unwrap();
+        bytes.by_ref().take(3).for_each(drop);
+
+        let mut chunks = Vec::new();
+        bytes.pump(|chunk| { chunks.push(chunk.to_vec()); Ok(()) }).unwrap();
+        // 最初は未消費の残り
+        assert_eq!(chunks[0], &base[3..8]);
+        assert_eq!(chunks.concat(), &base[3..]);
+    }
+
+    // fのエラーで止まり、そのチャンクから読み直せる
+    #[test]
+    fn pump_error_test() {
+        let mut bytes = BufBytes::with_capacity(&b"abcdefgh"[..], 4).unwrap();
+        let mut calls = 0;
+        let err = bytes.pump(|chunk| {
+            calls += 1;
+            if chunk == b"efgh" { Err(std::io::Error::other("stop")) } else { Ok(()) }
+        }).unwrap_err();
+        assert_eq!(err.to_string(), "stop");
+        assert_eq!(calls, 2);
+        assert_eq!(bytes.collect::<Vec<u8>>(), b"efgh");
+    }
+
+    // 境界をまたぐ終端も見つけて、終端まで消費する
+    #[test]
+    fn read_until_pattern_test() {
+        let base = b"GET / HTTP/1.1\r\nHost: a\r\n\r\nbody";
+        for size in [1, 3, 5, 64] {
+            let mut bytes = BufBytes::with_capacity(&base[..], size).unwrap();
+            let mut out = Vec::new();
+            assert!(bytes.read_until_pattern(b"\r\n\r\n", &mut out).unwrap());
+            assert_eq!(out, b"GET / HTTP/1.1\r\nHost: a\r\n\r\n");
+
+            // 見つからなければ、残りを全部追記してfalse
+            out.clear();
+            assert!(!bytes.read_until_pattern(b"\r\n\r\n", &mut out).unwrap());
+            assert_eq!(out, b"body");
+        }
+    }
+
+    // Breakで途中停止して、残りが読める
+    #[test]
+    fn drive_break_test() {
+        let base = b"123456,rest";
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        // 数字を読んで数値にするステートマシン
+        let mut num = 0u32;
+        bytes.drive(&mut num, |num, b| {
+            if b.is_ascii_digit() {
+                *num = *num * 10 + (b - b'0') as u32;
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        }).unwrap();
+
+        assert_eq!(num, 123456);
+        // Breakした','は消費していない
+        assert_eq!(bytes.collect::<Vec<u8>>(), b",rest");
+    }
+
+    // 最後までContinueならEOFまで読む
+    #[test]
+    fn drive_to_end_test() {
+        let base = b"hello world";
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        let mut count = 0;
+        bytes.drive(&mut count, |count, _| {
+            *count += 1;
+            ControlFlow::Continue(())
+        }).unwrap();
+
+        assert_eq!(count, base.len());
+        assert_eq!(bytes.next(), None);
+    }
+
+    // 区切りがバッファ境界をまたいでも、区切りまでを読む
+    #[test]
+    fn read_until_test() {
+        let base = b"first line\nsecond\nlast";
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        let mut out = Vec::new();
+        assert_eq!(bytes.read_until(b'\n', &mut out).unwrap(), 11);
+        assert_eq!(out, b"first line\n");
+        out.clear();
+        assert_eq!(bytes.read_until(b'\n', &mut out).unwrap(), 7);
+        assert_eq!(out, b"second\n");
+        out.clear();
+        // 区切りが来ないままEOF
+        assert_eq!(bytes.read_until(b'\n', &mut out).unwrap(), 4);
+        assert_eq!(out, b"last");
+        assert_eq!(bytes.read_until(b'\n', &mut out).unwrap(), 0);
+    }
+
+    // 同一なら None
+    #[test]
+    fn diff_offset_same_test() {
+        let base: Vec<u8> = (0..100).collect();
+        let mut a = BufBytes::with_capacity(&base[..], 7).unwrap();
+        let mut b = BufBytes::with_capacity(&base[..], 16).unwrap();
+
+        assert_eq!(a.diff_offset(&mut b).unwrap(), None);
+    }
+
+    // 食い違うバイトのオフセットを返し、そのバイトは消費しない
+    #[test]
+    fn diff_offset_different_test() {
+        let base: Vec<u8> = (0..100).collect();
+        let mut other = base.clone();
+        other[42] = 0xff;
+        let mut a = BufBytes::with_capacity(&base[..], 7).unwrap();
+        let mut b = BufBytes::with_capacity(&other[..], 16).unwrap();
+
+        assert_eq!(a.diff_offset(&mut b).unwrap(), Some(42));
+        assert_eq!(a.next(), Some(42));
+        assert_eq!(b.next(), Some(0xff));
+    }
+
+    // 長さが違えば、短い方の終わりが食い違い
+    #[test]
+    fn diff_offset_length_test() {
+        let base: Vec<u8> = (0..100).collect();
+        let mut a = BufBytes::with_capacity(&base[..], 7).unwrap();
+        let mut b = BufBytes::with_capacity(&base[..60], 16).unwrap();
+        assert_eq!(a.diff_offset(&mut b).unwrap(), Some(60));
+
+        let mut a = BufBytes::with_capacity(&base[..60], 7).unwrap();
+        let mut b = BufBytes::with_capacity(&base[..], 16).unwrap();
+        assert_eq!(a.diff_offset(&mut b).unwrap(), Some(60));
+    }
+
+    // どのデリミタが先に来ても止まる
+    #[test]
+    fn read_until_any_test() {
+        let base = b"a=1;b=2\nlong value without delimiter";
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+
+        let mut out = Vec::new();
+        assert_eq!(bytes.read_until_any(b"\n;", &mut out).unwrap(), Some(b';'));
+        assert_eq!(out, b"a=1;");
+        out.clear();
+        assert_eq!(bytes.read_until_any(b"\n;", &mut out).unwrap(), Some(b'\n'));
+        assert_eq!(out, b"b=2\n");
+        out.clear();
+        assert_eq!(bytes.read_until_any(b"\n;", &mut out).unwrap(), None);
+        assert_eq!(out, b"long value without delimiter");
+    }
+}
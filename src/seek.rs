@@ -0,0 +1,547 @@
+//! baseがSeekできるときに使えるメソッドたち
+
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom};
+
+use crate::BufBytes;
+
+/// 読み取り位置のブックマーク
+/// 
+/// [`BufBytes::mark`]で作成し、[`BufBytes::reset_to`]で戻ります。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bookmark {
+    offset: u64,
+}
+
+impl Bookmark {
+    /// ブックマークした読み取り位置
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read + Seek,
+{
+    /// ストリームの大きさに合わせたバッファでBufBytesを作成
+    /// 
+    /// 残りのサイズと8192の小さい方をバッファーサイズにします。  
+    /// 数バイトしかない小さなソースを大量に処理するときに、無駄な確保を減らせます。
+    pub fn new_auto(mut base: B) -> Result<Self> {
+        let pos = base.stream_position()?;
+        let len = base.seek(SeekFrom::End(0))?;
+        base.seek(SeekFrom::Start(pos))?;
+        let size = len.saturating_sub(pos).clamp(1, 8192) as usize;
+        let mut bytes = Self::unfilled(base, vec![0; size]);
+        bytes.stream_len = Some(len);
+        bytes.first_fill()
+    }
+
+    /// baseのoffsetの位置から読み始めるBufBytesを作成
+    /// 
+    /// [`checkpoint`](Self::checkpoint)で保存したオフセットから、中断した読み込みを再開するのに使います。  
+    /// baseを`SeekFrom::Start(offset)`へseekしてから、[`with_capacity`](Self::with_capacity)と同じように読み込みます。
+    pub fn resume_from(mut base: B, size: usize, offset: u64) -> Result<Self> {
+        base.seek(SeekFrom::Start(offset))?;
+        Self::with_capacity(base, size)
+    }
+
+    /// 再開用に、今の読み取り位置をbaseの先頭からのオフセットで返す
+    /// 
+    /// [`position`](Self::position)と違い、作成時の位置ではなくbaseの先頭から数えるので、  
+    /// ファイルなどに保存しておけば、別のプロセスからでも[`resume_from`](Self::resume_from)で再開できます。  
+    /// [`unread`](Self::unread)で押し戻したバイトも、再開したら読み直すものとして数えます。  
+    /// 押し戻しすぎて、位置がbaseの先頭より前になったときは`InvalidInput`を返します。
+    pub fn checkpoint(&mut self) -> Result<u64> {
+        let unconsumed = (self.buffer().len() + self.ahead_len) as u64;
+        self.base.stream_position()?.checked_sub(unconsumed)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "unread past the start of base"))
+    }
+
+    /// 現在の読み取り位置をブックマークする
+    pub fn mark(&self) -> Bookmark {
+        Bookmark { offset: Self::position(self) }
+    }
+
+    /// ブックマークした位置に戻る
+    /// 
+    /// baseをseekし、バッファは捨てて読み直します。  
+    /// 複数のブックマークを、どの順番で使っても構いません。
+    pub fn reset_to(&mut self, mark: Bookmark) -> Result<()> {
+        self.seek_to(mark.offset)
+    }
+
+    /// 読み取り位置が、baseの実際の位置と合っているか調べる
+    /// 
+    /// 「読み取り位置 + 未消費のバイト数」が`base.stream_position()`と一致すれば`true`です。  
+    /// refillのバグなどで内部状態がずれていないかの、自己診断に使います。  
+    /// 読み取り位置は作成時からの相対値なので、baseの先頭から読み始めたときだけ意味があります。
+    pub fn verify_position(&mut self) -> Result<bool> {
+        let unconsumed = (self.buffer().len() + self.ahead_len) as u64;
+        Ok(self.base.stream_position()? == Self::position(self) + unconsumed)
+    }
+
+    /// offsetからlenバイトを読む
+    /// 
+    /// インデックス付きのファイル形式などで、決まった位置を拾い読みするのに使います。  
+    /// offsetは読み取り位置と同じく、作成時からの相対値です。  
+    /// baseから直接読み、終えたらbaseの位置を戻すので、`next()`などの連続読みと混ぜても構いません。  
+    /// 読み取り位置やバッファ([`unread`](Self::unread)で押し戻したバイトも)はそのままで、  
+    /// 読んだバイトは消費したことにしません。([`recorded`](Self::recorded)や[`parity`](Self::parity)などに入りません)  
+    /// (範囲がバッファの中に収まっていれば、seekせずにバッファから返します)  
+    /// lenバイトに満たずにEOFになったら`UnexpectedEof`を、範囲がu64に収まらなければ`InvalidInput`を、  
+    /// io処理でエラーが起きたらそのエラーを返します。 このときのエラーは[`get_err`](Self::get_err)には残しません。
+    pub fn read_at(&mut self, offset: u64, len: usize) -> Result<Vec<u8>> {
+        let end = offset.checked_add(len as u64)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "read_at range overflows u64"))?;
+        let pos = Self::position(self);
+        let buf = self.buffer();
+        if offset >= pos && end <= pos + buf.len() as u64 {
+            let start = (offset - pos) as usize;
+            return Ok(buf[start..start + len].to_vec());
+        }
+        let saved = self.base.stream_position()?;
+        let start = Self::base_origin(saved, self.read_total)?.checked_add(offset)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "read_at range overflows u64"))?;
+        let mut out = vec![0; len];
+        let res = self.base.seek(SeekFrom::Start(start)).and_then(|_| self.base.read_exact(&mut out));
+        self.base.seek(SeekFrom::Start(saved))?;
+        res.map(|()| out)
+    }
+
+    /// 未消費の範囲で、最後にneedleが出てくるオフセットを探す
+    /// 
+    /// 拡張子のドットや、ファイル末尾のマーカーを探すのに使えます。  
+    /// 末尾からバッファーサイズのブロックごとに、逆向きにseekして読み込みます。  
+    /// オフセットは[`read_at`](Self::read_at)と同じく作成時からの相対値で、読み取り位置より前は探しません。  
+    /// 読み取り位置やバッファはそのままです。 io処理でエラーが起きたらそのエラーを返し、[`get_err`](Self::get_err)には残しません。  
+    /// 作成したときの位置より短く切り詰められていたら、`None`を返します。
+    pub fn rfind_byte(&mut self, needle: u8) -> Result<Option<u64>> {
+        let pos = Self::position(self);
+        let saved = self.base.stream_position()?;
+        // baseの位置はread_totalに当たるので、相対値の0がbaseのどこかを求める
+        let origin = Self::base_origin(saved, self.read_total)?;
+        let Some(end) = self.update_stream_len()?.checked_sub(origin) else {
+            return Ok(None);
+        };
+        let mut block = vec![0; self.buf.len()];
+        let res = (|| {
+            let mut cur = end;
+            while cur > pos {
+                let start = cur.saturating_sub(block.len() as u64).max(pos);
+                let chunk = &mut block[..(cur - start) as usize];
+                self.base.seek(SeekFrom::Start(origin + start))?;
+                self.base.read_exact(chunk)?;
+                if let Some(i) = chunk.iter().rposition(|&b| b == needle) {
+                    return Ok(Some(start + i as u64));
+                }
+                cur = start;
+            }
+            Ok(None)
+        })();
+        self.base.seek(SeekFrom::Start(saved))?;
+        res
+    }
+
+    /// 読み取り位置がストリームの終端に達しているか調べる
+    /// 
+    /// バッファに未消費のバイトがあれば、まだ終端ではありません。  
+    /// バッファが空なら、baseの位置とサイズを比べます。(読み込みは起こしません)
+    pub fn at_end(&mut self) -> Result<bool> {
+        if self.buffer().len() + self.ahead_len > 0 {
+            return Ok(false);
+        }
+        let pos = self.base.stream_position()?;
+        Ok(pos >= self.update_stream_len()?)
+    }
+
+    /// baseが読み取り中にトランケートされていないか調べる
+    /// 
+    /// baseの今のサイズを調べ、前回調べたときより小さくなっているか、  
+    /// すでに読み込んだ位置より小さくなっていれば`true`を返します。  
+    /// (最初の呼び出しでは、読み込んだ位置との比較だけになります)
+    pub fn detect_truncation(&mut self) -> Result<bool> {
+        let pos = self.base.stream_position()?;
+        let prev = self.stream_len;
+        let len = self.update_stream_len()?;
+        Ok(prev.is_some_and(|prev| len < prev) || len < pos)
+    }
+
+    /// 読み取りの進み具合を、0.0から1.0の割合で返す
+    /// 
+    /// 「読み取り位置 / baseのサイズ」です。 サイズが0のときは、読み終えたものとして1.0を返します。  
+    /// 何度も呼ばれても余計なseekをしないよう、サイズはキャッシュしたものを使います。  
+    /// ([`new_auto`](Self::new_auto)なら作成時に、それ以外は最初の呼び出しで調べます)
+    pub fn progress_ratio(&mut self) -> Result<f64> {
+        let len = match self.stream_len {
+            Some(len) => len,
+            None => self.update_stream_len()?,
+        };
+        if len == 0 {
+            return Ok(1.0);
+        }
+        Ok((Self::position(self) as f64 / len as f64).min(1.0))
+    }
+
+    /// 読み取り位置の0が、baseの先頭から何バイト目かを求める
+    /// 
+    /// baseの位置(saved)は、読み込んだ総バイト数(read_total)に当たる
+    fn base_origin(saved: u64, read_total: u64) -> Result<u64> {
+        saved.checked_sub(read_total)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "base is behind the bytes read from it"))
+    }
+
+    /// baseのサイズを調べ直してキャッシュする
+    /// 
+    /// baseの位置は元に戻します。
+    fn update_stream_len(&mut self) -> Result<u64> {
+        let pos = self.base.stream_position()?;
+        let len = self.base.seek(SeekFrom::End(0))?;
+        self.base.seek(SeekFrom::Start(pos))?;
+        self.stream_len = Some(len);
+        Ok(len)
+    }
+
+    /// 読み取り位置をoffsetへ移す
+    /// 
+    /// 読み取り位置は作成時からの相対値なので、baseの今の位置との差分でseekします。
+    fn seek_to(&mut self, offset: u64) -> Result<()> {
+        self.sync_consumed();
+        let diff = offset as i64 - self.read_total as i64;
+        self.base.seek(SeekFrom::Current(diff))?;
+        self.read_total = offset;
+        self.last_good = offset;
+        // 読み込み済みのデータは位置がずれるので捨てる
+        self.set_buf_ptr(0);
+        self.ahead_len = 0;
+        self.fill_ahead();
+        Ok(())
+    }
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read + Seek + Clone,
+{
+    /// 今の読み取り位置から読み始める、独立したBufBytesを作る
+    /// 
+    /// baseを`clone`して、今の読み取り位置までseekし直したものを使います。  
+    /// 読み取り位置は元と同じ基準で数えるので、[`mark`](Self::mark)や[`read_at`](Self::read_at)のoffsetは  
+    /// どちらのBufBytesでも同じ場所をさします。  
+    /// 以降はそれぞれ別々に読み進められます。 (`Cursor`のように、cloneで位置が独立するbaseで使ってください)  
+    /// バッファーサイズは元と同じで、最初の`next()`で読み込みます。  
+    /// [`unread`](Self::unread)で押し戻したバイトは引き継がず、その位置からbaseの中身を読みます。
+    pub fn fork(&self) -> Result<Self> {
+        let mut base = self.base.clone();
+        // baseの位置はread_totalに当たるので、そこから読み取り位置の0を求めてseekする
+        let origin = Self::base_origin(base.stream_position()?, self.read_total)?;
+        let pos = Self::position(self);
+        base.seek(SeekFrom::Start(origin + pos))?;
+        let mut bytes = Self::unfilled(base, vec![0; self.buf.len()]);
+        bytes.read_total = pos;
+        bytes.last_good = bytes.read_total;
+        bytes.stream_len = self.stream_len;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Seek, Write};
+
+    use tempfile::NamedTempFile;
+
+    use crate::{BufBytes, Bookmark};
+
+    // 複数のブックマークを、任意の順で戻れる
+    #[test]
+    fn bookmark_test() {
+        let base = Cursor::new(b"0123456789abcdef".to_vec());
+        let mut bytes = BufBytes::with_capacity(base, 4).unwrap();
+
+        let mark0 = bytes.mark();
+        bytes.by_ref().take(3).for_each(drop);
+        let mark3 = bytes.mark();
+        bytes.by_ref().take(7).for_each(drop);
+        let mark10 = bytes.mark();
+        assert_eq!(mark3.offset(), 3);
+        assert_eq!(mark10.offset(), 10);
+
+        bytes.reset_to(mark3).unwrap();
+        assert_eq!(bytes.next(), Some(b'3'));
+        bytes.reset_to(mark10).unwrap();
+        assert_eq!(bytes.next(), Some(b'a'));
+        bytes.reset_to(mark0).unwrap();
+        assert_eq!(bytes.position(), 0);
+        assert_eq!(bytes.collect::<Vec<u8>>(), b"0123456789abcdef");
+    }
+
+    // 先読みモードでもブックマークできる
+    #[test]
+    fn bookmark_eager_test() {
+        let base = Cursor::new(b"0123456789abcdef".to_vec());
+        let mut bytes = BufBytes::with_eager_error(base, 4).unwrap();
+
+        bytes.by_ref().take(5).for_each(drop);
+        let mark = bytes.mark();
+        assert_eq!(mark.offset(), 5);
+
+        bytes.by_ref().take(9).for_each(drop);
+        bytes.reset_to(mark).unwrap();
+        assert_eq!(bytes.collect::<Vec<u8>>(), b"56789abcdef");
+    }
+
+    // 読み取り中にトランケートされたファイルを検知する
+    #[test]
+    fn detect_truncation_test() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&[0; 100]).unwrap();
+        file.flush().unwrap();
+        file.rewind().unwrap();
+
+        let mut bytes = BufBytes::with_capacity(file.reopen().unwrap(), 16).unwrap();
+        bytes.by_ref().take(30).for_each(drop);
+        assert!(!bytes.detect_truncation().unwrap());
+
+        // 別のハンドルから、まだ読んでいない部分を切り詰める
+        file.as_file().set_len(50).unwrap();
+        assert!(bytes.detect_truncation().unwrap());
+        // 前回と同じサイズで、読み込み済みの位置(32byte)より大きいのでfalse
+        assert!(!bytes.detect_truncation().unwrap());
+
+        // 読み込み済みの位置より前まで切り詰める
+        file.as_file().set_len(10).unwrap();
+        assert!(bytes.detect_truncation().unwrap());
+        assert!(bytes.detect_truncation().unwrap());
+    }
+
+    // 正常時はtrue、状態を壊すとfalse
+    #[test]
+    fn verify_position_test() {
+        let base = Cursor::new((0..100).collect::<Vec<u8>>());
+        let mut bytes = BufBytes::with_eager_error(base, 16).unwrap();
+        assert!(bytes.verify_position().unwrap());
+
+        bytes.by_ref().take(37).for_each(drop);
+        bytes.peek_n(20).unwrap();
+        assert!(bytes.verify_position().unwrap());
+        bytes.reset_to(bytes.mark()).unwrap();
+        assert!(bytes.verify_position().unwrap());
+
+        // 読み込んだバイト数を意図的にずらす
+        bytes.read_total += 1;
+        assert!(!bytes.verify_position().unwrap());
+    }
+
+    // 末尾直前とちょうど末尾
+    #[test]
+    fn at_end_test() {
+        let base = Cursor::new((0..8).collect::<Vec<u8>>());
+        let mut bytes = BufBytes::with_capacity(base, 4).unwrap();
+        assert!(!bytes.at_end().unwrap());
+
+        // バッファ境界でも、まだ続きがあれば終端ではない
+        bytes.by_ref().take(4).for_each(drop);
+        assert!(!bytes.at_end().unwrap());
+
+        bytes.by_ref().take(3).for_each(drop);
+        assert!(!bytes.at_end().unwrap());
+        assert_eq!(bytes.next(), Some(7));
+        assert!(bytes.at_end().unwrap());
+        assert_eq!(bytes.next(), None);
+    }
+
+    // 拾い読みしたバイトは消費したことにならず、押し戻したバイトも残る
+    #[test]
+    fn read_at_unread_recording_test() {
+        let data: Vec<u8> = (0..100).collect();
+        let mut bytes = BufBytes::with_recording(Cursor::new(data), 16).unwrap();
+        bytes.by_ref().take(2).for_each(drop);
+
+        assert_eq!(bytes.read_at(50, 4).unwrap(), [50, 51, 52, 53]);
+        assert_eq!(bytes.recorded(), [0, 1]);
+        assert_eq!(bytes.checksum_add(), 1);
+
+        bytes.unread(&[0xaa]).unwrap();
+        assert_eq!(bytes.read_at(60, 2).unwrap(), [60, 61]);
+        assert_eq!(bytes.next(), Some(0xaa));
+        assert_eq!(bytes.next(), Some(2));
+        assert_eq!(bytes.recorded(), [0, 1, 0xaa, 2]);
+
+        let err = bytes.read_at(u64::MAX, 2).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    // 拾い読みしても、連続読みの続きは変わらない
+    #[test]
+    fn read_at_test() {
+        let data: Vec<u8> = (0..=255).collect();
+        let mut bytes = BufBytes::with_capacity(Cursor::new(data), 16).unwrap();
+        bytes.by_ref().take(10).for_each(drop);
+
+        for (offset, len) in [(200, 4), (3, 2), (12, 3), (0, 1), (250, 6), (100, 40)] {
+            let expected: Vec<u8> = (offset..offset + len as u64).map(|v| v as u8).collect();
+            assert_eq!(bytes.read_at(offset, len).unwrap(), expected);
+        }
+        assert_eq!(BufBytes::position(&bytes), 10);
+        assert_eq!(bytes.next(), Some(10));
+
+        // 終端を越えるとUnexpectedEofで、位置は戻る
+        let err = bytes.read_at(254, 4).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert!(bytes.get_err().is_none());
+        assert_eq!(bytes.next(), Some(11));
+    }
+
+    // フォークしたカーソルは、お互いに干渉しない
+    #[test]
+    fn fork_test() {
+        let data: Vec<u8> = (0..100).collect();
+        let mut bytes = BufBytes::with_capacity(Cursor::new(data), 16).unwrap();
+        bytes.by_ref().take(5).for_each(drop);
+
+        let mut fork = bytes.fork().unwrap();
+        assert_eq!(BufBytes::position(&fork), 5);
+        fork.reset_to(Bookmark { offset: 50 }).unwrap();
+        assert_eq!(fork.by_ref().take(3).collect::<Vec<u8>>(), [50, 51, 52]);
+
+        // 元のBufBytesは、フォークに関係なく続きを読む
+        assert_eq!(bytes.by_ref().take(3).collect::<Vec<u8>>(), [5, 6, 7]);
+        assert_eq!(fork.next(), Some(53));
+        assert_eq!(bytes.count(), 92);
+        assert_eq!(fork.count(), 46);
+    }
+
+    // 押し戻した後にフォークしても、同じ読み取り位置からbaseを読む
+    #[test]
+    fn fork_unread_test() {
+        let data: Vec<u8> = (0..100).collect();
+        let mut bytes = BufBytes::with_capacity(Cursor::new(data), 16).unwrap();
+        bytes.by_ref().take(5).for_each(drop);
+        bytes.unread(&[3, 4]).unwrap();
+
+        let mut fork = bytes.fork().unwrap();
+        assert_eq!(BufBytes::position(&fork), 3);
+        assert_eq!(fork.by_ref().take(3).collect::<Vec<u8>>(), [3, 4, 5]);
+        assert_eq!(fork.read_at(10, 2).unwrap(), [10, 11]);
+
+        // 読み込んだ以上に押し戻したときは、先頭から読む
+        bytes.unread(&[9; 10]).unwrap();
+        let mut fork = bytes.fork().unwrap();
+        assert_eq!(BufBytes::position(&fork), 0);
+        assert_eq!(fork.next(), Some(0));
+    }
+
+    // 末尾近く・先頭近く・ブロックの境界・見つからないとき
+    #[test]
+    fn rfind_byte_test() {
+        let mut data = vec![0u8; 1000];
+        let mut bytes = BufBytes::with_capacity(Cursor::new(data.clone()), 16).unwrap();
+        assert_eq!(bytes.rfind_byte(b'.').unwrap(), None);
+
+        for (i, expected) in [(3, 3), (32, 32), (31, 32), (990, 990)] {
+            data[i] = b'.';
+            let mut bytes = BufBytes::with_capacity(Cursor::new(data.clone()), 16).unwrap();
+            assert_eq!(bytes.rfind_byte(b'.').unwrap(), Some(expected));
+        }
+    }
+
+    // 読み取り位置より前は探さず、読み取り位置も変えない
+    #[test]
+    fn rfind_byte_position_test() {
+        let mut data = vec![0u8; 100];
+        data[5] = b'.';
+        data[20] = b'.';
+        let mut bytes = BufBytes::with_capacity(Cursor::new(data), 16).unwrap();
+        bytes.by_ref().take(10).for_each(drop);
+        assert_eq!(bytes.rfind_byte(b'.').unwrap(), Some(20));
+        assert_eq!(bytes.position(), 10);
+        assert_eq!(bytes.by_ref().position(|b| b == b'.'), Some(10));
+        assert_eq!(bytes.rfind_byte(b'.').unwrap(), None);
+    }
+
+    // 作成したときの位置より短く切り詰められたら、見つからないことにする
+    #[test]
+    fn rfind_byte_truncated_test() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&[b'.'; 100]).unwrap();
+        file.flush().unwrap();
+
+        let mut reader = file.reopen().unwrap();
+        reader.seek(std::io::SeekFrom::Start(50)).unwrap();
+        let mut bytes = BufBytes::with_capacity(reader, 16).unwrap();
+        assert_eq!(bytes.rfind_byte(b'.').unwrap(), Some(49));
+
+        file.as_file().set_len(20).unwrap();
+        assert_eq!(bytes.rfind_byte(b'.').unwrap(), None);
+        assert_eq!(bytes.next(), Some(b'.'));
+    }
+
+    // checkpointから再開すると、中断しなかったときと同じ続きが読める
+    #[test]
+    fn checkpoint_resume_test() {
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let mut bytes = BufBytes::with_eager_error(Cursor::new(data.clone()), 64).unwrap();
+        bytes.by_ref().take(300).for_each(drop);
+        let offset = bytes.checkpoint().unwrap();
+        assert_eq!(offset, 300);
+        let rest: Vec<u8> = bytes.collect();
+
+        // 再開した後のcheckpointも、baseの先頭から数える
+        let mut resumed = BufBytes::resume_from(Cursor::new(data.clone()), 64, offset).unwrap();
+        resumed.by_ref().take(100).for_each(drop);
+        assert_eq!(resumed.checkpoint().unwrap(), 400);
+
+        let resumed = BufBytes::resume_from(Cursor::new(data), 64, offset).unwrap();
+        assert_eq!(resumed.collect::<Vec<u8>>(), rest);
+    }
+
+    // 押し戻したバイトの分だけcheckpointも戻り、baseの先頭より前ならエラーになる
+    #[test]
+    fn checkpoint_unread_test() {
+        let data: Vec<u8> = (0..100).collect();
+        let mut bytes = BufBytes::with_capacity(Cursor::new(data), 16).unwrap();
+        bytes.by_ref().take(5).for_each(drop);
+        bytes.unread(&[3, 4]).unwrap();
+        assert_eq!(bytes.checkpoint().unwrap(), 3);
+
+        let mut bytes = BufBytes::with_capacity(Cursor::new(vec![0u8; 100]), 16).unwrap();
+        bytes.next();
+        bytes.unread(&[9; 10]).unwrap();
+        let err = bytes.checkpoint().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    // 半分読めば0.5、全部読めば1.0
+    #[test]
+    fn progress_ratio_test() {
+        let base = Cursor::new(vec![0; 1000]);
+        let mut bytes = BufBytes::with_capacity(base, 64).unwrap();
+        assert_eq!(bytes.progress_ratio().unwrap(), 0.0);
+
+        bytes.by_ref().take(500).for_each(drop);
+        assert!((bytes.progress_ratio().unwrap() - 0.5).abs() < 1e-9);
+        bytes.by_ref().for_each(drop);
+        assert_eq!(bytes.progress_ratio().unwrap(), 1.0);
+    }
+
+    // 小さいファイルでは、バッファがファイルサイズに抑えられる
+    #[test]
+    fn new_auto_test() {
+        let bytes = BufBytes::new_auto(Cursor::new(b"tiny".to_vec())).unwrap();
+        assert_eq!(bytes.buf.len(), 4);
+        assert_eq!(bytes.collect::<Vec<u8>>(), b"tiny");
+
+        let bytes = BufBytes::new_auto(Cursor::new(vec![0; 100000])).unwrap();
+        assert_eq!(bytes.buf.len(), 8192);
+        assert_eq!(bytes.count(), 100000);
+
+        // 途中から読むときは、残りのサイズ
+        let mut base = Cursor::new(vec![0; 100]);
+        base.set_position(90);
+        let bytes = BufBytes::new_auto(base).unwrap();
+        assert_eq!(bytes.buf.len(), 10);
+
+        assert!(BufBytes::new_auto(Cursor::new(Vec::new())).is_err());
+    }
+}
@@ -0,0 +1,97 @@
+//! バッファを借用する軽量なカーソル
+
+use std::io::Read;
+
+use crate::BufBytes;
+
+/// 未消費のバッファを借用して読む、軽量なカーソル
+/// 
+/// [`BufBytes::snapshot`]で作成します。  
+/// io処理は起こさず、作成時にバッファに入っていた範囲だけを読めます。  
+/// いくら読み進めても、元の`BufBytes`の読み取り位置は変わりません。
+#[derive(Debug, Clone)]
+pub struct Snapshot<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl Snapshot<'_> {
+    /// 次に返すバイトを、読み進めずに返す
+    pub fn peek(&self) -> Option<u8> {
+        self.buf.get(self.pos).copied()
+    }
+
+    /// まだ読んでいない部分のスライス
+    pub fn remaining(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    /// スナップショットを作ってから読み進めたバイト数
+    pub fn consumed(&self) -> usize {
+        self.pos
+    }
+}
+
+impl Iterator for Snapshot<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.buf.len() - self.pos;
+        (len, Some(len))
+    }
+}
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 未消費のバッファを借用するスナップショットを作る
+    /// 
+    /// バッファに入っている範囲での、投機的なパースに使えます。  
+    /// もっと先まで試したいときは、先に[`peek_n`](Self::peek_n)でバッファを読み足してください。
+    pub fn snapshot(&self) -> Snapshot<'_> {
+        Snapshot { buf: self.buffer(), pos: 0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    // スナップショットを消費しても、本体の位置は動かない
+    #[test]
+    fn snapshot_test() {
+        let base = b"key=value;rest";
+        let mut bytes = BufBytes::with_capacity(&base[..], 64).unwrap();
+        bytes.next();
+
+        let mut snap = bytes.snapshot();
+        assert_eq!(snap.peek(), Some(b'e'));
+        let key: Vec<u8> = snap.by_ref().take_while(|&b| b != b'=').collect();
+        assert_eq!(key, b"ey");
+        assert_eq!(snap.remaining(), b"value;rest");
+        assert_eq!(snap.consumed(), 3);
+
+        assert_eq!(bytes.position(), 1);
+        assert_eq!(bytes.collect::<Vec<u8>>(), &base[1..]);
+    }
+
+    // バッファに入っている範囲までしか読めない
+    #[test]
+    fn snapshot_buffer_only_test() {
+        let base = b"0123456789";
+        let mut bytes = BufBytes::with_capacity(&base[..], 4).unwrap();
+        bytes.next();
+
+        assert_eq!(bytes.snapshot().collect::<Vec<u8>>(), b"123");
+        // 読み足すと、スナップショットの範囲も広がる
+        bytes.peek_n(6).unwrap();
+        assert_eq!(bytes.snapshot().collect::<Vec<u8>>(), b"123456");
+    }
+}
@@ -0,0 +1,72 @@
+//! 残りのバイトから統計を求めるメソッドたち
+
+use std::io::{Read, Result};
+
+use crate::BufBytes;
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// 残りのバイトについて、バイトの値ごとの出現回数を数える
+    /// 
+    /// `histogram()[b as usize]`がバイトbの個数です。  
+    /// 最後まで消費します。 io処理でエラーが起きたら、そのエラーを返します。
+    pub fn histogram(&mut self) -> Result<[u64; 256]> {
+        let hist = self.fold_chunks([0; 256], |mut hist, buf| {
+            buf.iter().for_each(|&b| hist[b as usize] += 1);
+            hist
+        });
+        match self.error {
+            Some(_) => Err(self.short_read_error()),
+            None => Ok(hist),
+        }
+    }
+
+    /// 残りのバイトのシャノンエントロピー(bits/byte)を求める
+    /// 
+    /// 0.0(全部同じバイト)から8.0(全部の値が均等に出る)の間になります。  
+    /// 8.0に近ければ、圧縮済みか暗号化されたデータだと推測できます。  
+    /// 空なら0.0です。 [`histogram`](Self::histogram)と同じく最後まで消費します。
+    pub fn entropy(&mut self) -> Result<f64> {
+        let hist = self.histogram()?;
+        let total: u64 = hist.iter().sum();
+        if total == 0 {
+            return Ok(0.0);
+        }
+        let entropy = hist.iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / total as f64;
+                -p * p.log2()
+            })
+            .sum();
+        Ok(entropy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BufBytes;
+
+    #[test]
+    fn histogram_test() {
+        let mut bytes = BufBytes::with_capacity(&b"abracadabra"[..], 4).unwrap();
+        let hist = bytes.histogram().unwrap();
+        assert_eq!(hist[b'a' as usize], 5);
+        assert_eq!(hist[b'b' as usize], 2);
+        assert_eq!(hist[b'z' as usize], 0);
+        assert_eq!(hist.iter().sum::<u64>(), 11);
+    }
+
+    // 全部同じなら0、均等なら8
+    #[test]
+    fn entropy_test() {
+        let same = [0x42; 1000];
+        assert_eq!(BufBytes::new(&same[..]).unwrap().entropy().unwrap(), 0.0);
+
+        let uniform: Vec<u8> = (0..=255).cycle().take(256 * 16).collect();
+        let entropy = BufBytes::with_capacity(&uniform[..], 100).unwrap().entropy().unwrap();
+        assert!((entropy - 8.0).abs() < 1e-9, "{}", entropy);
+    }
+}
@@ -0,0 +1,75 @@
+//! 消費したバイトを覚えておく、使うときだけ持つ記録
+//!
+//! [`with_rewind_cache`](crate::BufBytes::with_rewind_cache)、[`with_ring`](crate::BufBytes::with_ring)、
+//! [`with_recording`](crate::BufBytes::with_recording)、[`on_interval`](crate::BufBytes::on_interval)のどれかを使うときだけ作ります。
+//! 使わないBufBytesでは`None`のままにして、`next()`ではバッファが空になるまで何も調べずに返します。
+
+use std::collections::VecDeque;
+
+use crate::interval::IntervalHook;
+
+#[derive(Debug, Default)]
+pub(crate) struct Tracking {
+    // with_rewind_cacheで使う、最後に消費したバイトのキャッシュとその上限
+    pub(crate) rewind_cache: Option<VecDeque<u8>>,
+    pub(crate) rewind_limit: usize,
+    // with_ringで使う、直近に消費したバイトのリングとその容量
+    pub(crate) ring: Option<VecDeque<u8>>,
+    pub(crate) ring_capacity: usize,
+    // with_recordingで使う、消費したバイトをすべて記録したもの
+    pub(crate) recorded: Option<Vec<u8>>,
+    // on_intervalで登録したコールバック
+    pub(crate) interval: Option<IntervalHook>,
+}
+
+impl Tracking {
+    /// 消費したバイトを、巻き戻し用のキャッシュやリング、記録に足す
+    pub(crate) fn push(&mut self, consumed: &[u8]) {
+        if let Some(cache) = self.rewind_cache.as_mut() {
+            push_capped(cache, consumed, self.rewind_limit);
+        }
+        if let Some(ring) = self.ring.as_mut() {
+            push_capped(ring, consumed, self.ring_capacity);
+        }
+        if let Some(recorded) = self.recorded.as_mut() {
+            // zeroize featureなら、Vecに任せて確保し直さず、古い領域を消してから手放す
+            #[cfg(feature = "zeroize")]
+            if recorded.capacity() - recorded.len() < consumed.len() {
+                use zeroize::Zeroize;
+                let mut grown = Vec::with_capacity((recorded.len() + consumed.len()).max(recorded.capacity() * 2));
+                grown.extend_from_slice(recorded);
+                std::mem::replace(recorded, grown).zeroize();
+            }
+            recorded.extend_from_slice(consumed);
+        }
+    }
+}
+
+// zeroize featureでは、消費したバイトの記録も0で上書きしてから解放する
+#[cfg(feature = "zeroize")]
+impl Drop for Tracking {
+    fn drop(&mut self) {
+        use zeroize::Zeroize;
+        for deque in [self.rewind_cache.as_mut(), self.ring.as_mut()].into_iter().flatten() {
+            // 捨てた分が残っているところも消すため、容量いっぱいまで伸ばしてから消す
+            deque.clear();
+            deque.resize(deque.capacity(), 0);
+            let (front, back) = deque.as_mut_slices();
+            front.zeroize();
+            back.zeroize();
+        }
+        if let Some(recorded) = self.recorded.as_mut() {
+            recorded.zeroize();
+        }
+    }
+}
+
+/// consumedをcacheの後ろに足し、limitバイトを超えた古い分を捨てる
+/// 
+/// 先に古い分を捨てるので、cacheはlimitを超えて確保し直さない
+fn push_capped(cache: &mut VecDeque<u8>, consumed: &[u8], limit: usize) {
+    let consumed = &consumed[consumed.len().saturating_sub(limit)..];
+    let over = (cache.len() + consumed.len()).saturating_sub(limit);
+    cache.drain(..over);
+    cache.extend(consumed);
+}
@@ -0,0 +1,95 @@
+//! ブロックごとのハッシュで整合性を検証するメソッドたち(`sha2` feature)
+
+use std::io::{Error, ErrorKind, Read, Result};
+
+use sha2::{Digest, Sha256};
+
+use crate::BufBytes;
+
+impl<B> BufBytes<B>
+where
+    B: Read,
+{
+    /// chunk_sizeバイトごとのSHA-256が、expectedと順に一致するかを調べる
+    /// 
+    /// 最後のチャンクがchunk_sizeに満たないときは、残った分だけのハッシュと比べます。  
+    /// 一致しないチャンクが見つかったら、そこで読むのをやめて`false`を返します。  
+    /// チャンクの数がexpectedの個数と合わないときは`InvalidData`を、  
+    /// io処理でエラーが起きたときはそのエラーを返します。
+    /// 
+    /// # Panics
+    /// chunk_sizeが0のときはpanicします。
+    pub fn verify_chunks(&mut self, chunk_size: usize, expected: &[[u8; 32]]) -> Result<bool> {
+        assert!(chunk_size != 0, "chunk_size must be non-zero");
+        let mut chunk = vec![0; chunk_size];
+        let mut expected = expected.iter();
+        loop {
+            let n = self.fill_slice(&mut chunk);
+            if n < chunk_size && self.error.is_some() {
+                return Err(self.short_read_error());
+            }
+            if n == 0 {
+                break;
+            }
+            let Some(hash) = expected.next() else {
+                return Err(Error::new(ErrorKind::InvalidData, "more chunks than expected hashes"));
+            };
+            if Sha256::digest(&chunk[..n])[..] != hash[..] {
+                return Ok(false);
+            }
+            if n < chunk_size {
+                break;
+            }
+        }
+        if expected.next().is_some() {
+            return Err(Error::new(ErrorKind::InvalidData, "fewer chunks than expected hashes"));
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::BufBytes;
+
+    fn hash(hex: &str) -> [u8; 32] {
+        let mut out = [0; 32];
+        for (i, b) in out.iter_mut().enumerate() {
+            *b = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        out
+    }
+
+    // "abcd", "efgh", "ij"(端数)のハッシュ
+    fn expected() -> Vec<[u8; 32]> {
+        vec![
+            hash("88d4266fd4e6338d13b845fcf289579d209c897823b9217da3e161936f031589"),
+            hash("e5e088a0b66163a0a26a5e053d2a4496dc16ab6e0e3dd1adf2d16aa84a078c9d"),
+            hash("c9df9c3f2963b19b9b95f58c4d33b053fa9f8586dd6ee04126e52a868f882108"),
+        ]
+    }
+
+    #[test]
+    fn verify_chunks_test() {
+        let mut bytes = BufBytes::with_capacity(&b"abcdefghij"[..], 3).unwrap();
+        assert!(bytes.verify_chunks(4, &expected()).unwrap());
+
+        // 中身が違えばfalse
+        let mut bytes = BufBytes::with_capacity(&b"abcdefgXij"[..], 3).unwrap();
+        assert!(!bytes.verify_chunks(4, &expected()).unwrap());
+    }
+
+    // チャンクの数がハッシュの数と合わなければInvalidData
+    #[test]
+    fn verify_chunks_count_mismatch_test() {
+        let mut bytes = BufBytes::new(&b"abcdefghij"[..]).unwrap();
+        let err = bytes.verify_chunks(4, &expected()[..2]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let mut bytes = BufBytes::new(&b"abcdefgh"[..]).unwrap();
+        let err = bytes.verify_chunks(4, &expected()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
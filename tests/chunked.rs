@@ -0,0 +1,158 @@
+//! バッファやreadの区切り方を変えても、パース結果が変わらないことを確かめる
+//! 
+//! バッファ境界をまたぐところのバグは、区切り方を変えないと見つからないので、  
+//! `ChunkedReader`でいろいろな区切り方を試します。
+
+use std::io::{ErrorKind, Read, Result};
+
+use my_buf_bytes::BufBytes;
+use proptest::prelude::*;
+
+/// readごとに返すバイト数の決め方
+#[derive(Debug, Clone)]
+enum Chunking {
+    /// 1バイトずつ返す
+    OneByte,
+    /// 並びのバイト数を順に(繰り返し)返す
+    Sizes(Vec<usize>),
+    /// 一度に全部返す
+    All,
+}
+
+/// dataを、chunkingの区切り方で返す仮想ファイル
+struct ChunkedReader {
+    data: Vec<u8>,
+    cursor: usize,
+    chunking: Chunking,
+    reads: usize,
+}
+
+impl ChunkedReader {
+    fn new(data: &[u8], chunking: Chunking) -> Self {
+        Self { data: data.to_vec(), cursor: 0, chunking, reads: 0 }
+    }
+}
+
+impl Read for ChunkedReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let chunk = match &self.chunking {
+            Chunking::OneByte => 1,
+            Chunking::Sizes(sizes) => sizes[self.reads % sizes.len()],
+            Chunking::All => usize::MAX,
+        };
+        self.reads += 1;
+        let n = chunk.min(buf.len()).min(self.data.len() - self.cursor);
+        buf[..n].copy_from_slice(&self.data[self.cursor..self.cursor + n]);
+        self.cursor += n;
+        Ok(n)
+    }
+}
+
+// エラーは比べられないので、種類だけにする
+fn kinds<T>(iter: impl Iterator<Item = Result<T>>) -> Vec<std::result::Result<T, ErrorKind>> {
+    iter.map(|r| r.map_err(|e| e.kind())).collect()
+}
+
+/// 区切り方とバッファーサイズを変えて、fの結果がすべて同じか確かめる
+fn check_all_chunkings<T>(data: &[u8], sizes: &[usize], capacity: usize, f: impl Fn(BufBytes<ChunkedReader>) -> T)
+where
+    T: PartialEq + std::fmt::Debug,
+{
+    // 一度に全部読み込めたときを基準にする
+    let expected = f(BufBytes::with_capacity(ChunkedReader::new(data, Chunking::All), data.len().max(1)).unwrap());
+    for chunking in [Chunking::OneByte, Chunking::Sizes(sizes.to_vec()), Chunking::All] {
+        let bytes = BufBytes::with_capacity(ChunkedReader::new(data, chunking.clone()), capacity).unwrap();
+        assert_eq!(f(bytes), expected, "chunking: {chunking:?}, capacity: {capacity}");
+    }
+}
+
+// 区切りの文字が出やすいように、少ない文字から作る
+// (with_capacityは空のbaseをエラーにするので、どれも1バイト以上にする)
+fn text() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(prop::sample::select(b"ab0f,\"\n\r\xe3\x81\x82".to_vec()), 1..300)
+}
+
+fn binary() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(any::<u8>(), 1..300)
+}
+
+fn sizes() -> impl Strategy<Value = Vec<usize>> {
+    prop::collection::vec(1..20usize, 1..8)
+}
+
+proptest! {
+    #[test]
+    fn bytes_test(data in binary(), sizes in sizes(), capacity in 1..40usize) {
+        check_all_chunkings(&data, &sizes, capacity, |bytes| bytes.collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn read_array_test(data in binary(), sizes in sizes(), capacity in 1..40usize) {
+        check_all_chunkings(&data, &sizes, capacity, |mut bytes| {
+            let mut words = vec![];
+            while let Ok(word) = bytes.read_array::<4>() {
+                words.push(u32::from_le_bytes(word));
+            }
+            words
+        });
+    }
+
+    #[test]
+    fn as_u16_le_test(data in binary(), sizes in sizes(), capacity in 1..40usize) {
+        check_all_chunkings(&data, &sizes, capacity, |bytes| kinds(bytes.as_u16_le()));
+    }
+
+    #[test]
+    fn chars_utf32_le_test(data in binary(), sizes in sizes(), capacity in 1..40usize) {
+        check_all_chunkings(&data, &sizes, capacity, |bytes| kinds(bytes.chars_utf32_le()));
+    }
+
+    #[test]
+    fn lines_test(data in text(), sizes in sizes(), capacity in 1..40usize) {
+        check_all_chunkings(&data, &sizes, capacity, |bytes| kinds(bytes.lines()));
+    }
+
+    #[test]
+    fn read_until_test(data in text(), sizes in sizes(), capacity in 1..40usize) {
+        check_all_chunkings(&data, &sizes, capacity, |mut bytes| {
+            let mut parts = vec![];
+            loop {
+                let mut part = vec![];
+                if bytes.read_until(b'\n', &mut part).unwrap() == 0 {
+                    return parts;
+                }
+                parts.push(part);
+            }
+        });
+    }
+
+    #[test]
+    fn read_to_string_test(data in text(), sizes in sizes(), capacity in 1..40usize) {
+        check_all_chunkings(&data, &sizes, capacity, |mut bytes| {
+            let mut out = String::new();
+            bytes.read_to_string(&mut out).map_err(|e| e.kind()).map(|_| out)
+        });
+    }
+
+    #[test]
+    fn csv_fields_test(data in text(), sizes in sizes(), capacity in 1..40usize) {
+        check_all_chunkings(&data, &sizes, capacity, |bytes| kinds(bytes.csv_fields(b',')));
+    }
+
+    #[test]
+    fn hex_decode_test(data in text(), sizes in sizes(), capacity in 1..40usize) {
+        check_all_chunkings(&data, &sizes, capacity, |bytes| kinds(bytes.hex_decode()));
+    }
+
+    #[test]
+    fn find_pattern_test(data in text(), sizes in sizes(), capacity in 1..40usize) {
+        check_all_chunkings(&data, &sizes, capacity, |mut bytes| {
+            let mut found = vec![];
+            while let Some(offset) = bytes.find_pattern(b"a\n").unwrap() {
+                found.push(offset);
+                bytes.consume(1);
+            }
+            found
+        });
+    }
+}
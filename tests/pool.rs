@@ -0,0 +1,57 @@
+//! from_poolで、バッファの確保が減ることを確かめる
+//! 
+//! グローバルアロケータを差し替えるので、ほかのテストとは別のバイナリにしています。
+
+use std::{alloc::{GlobalAlloc, Layout, System}, cell::Cell};
+
+use my_buf_bytes::BufBytes;
+
+// このスレッドで確保したバイト数を数えるアロケータ
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATED.try_with(|n| n.set(n.get() + layout.size()));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATED.try_with(|n| n.set(n.get() + new_size));
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocated_by(f: impl FnOnce()) -> usize {
+    let before = ALLOCATED.with(Cell::get);
+    f();
+    ALLOCATED.with(Cell::get) - before
+}
+
+// 作っては捨てるのを繰り返しても、プールなら最初の1回しかバッファを確保しない
+#[test]
+fn from_pool_reuses_buffer_test() {
+    let data = b"request body";
+    let fresh = allocated_by(|| {
+        for _ in 0..100 {
+            assert_eq!(BufBytes::new(&data[..]).unwrap().count(), data.len());
+        }
+    });
+    let pooled = allocated_by(|| {
+        for _ in 0..100 {
+            assert_eq!(BufBytes::from_pool(&data[..]).unwrap().count(), data.len());
+        }
+    });
+    assert!(fresh >= 100 * 8192, "{}", fresh);
+    assert!(pooled < fresh / 10, "pooled: {}, fresh: {}", pooled, fresh);
+}
@@ -0,0 +1,105 @@
+//! zeroize featureで、解放した領域に読んだデータが残らないことを確かめる
+//! 
+//! 解放される直前の領域を調べるアロケータに差し替えるので、ほかのテストとは別のバイナリにしています。
+
+#![cfg(feature = "zeroize")]
+
+use std::{alloc::{GlobalAlloc, Layout, System}, cell::Cell};
+
+use my_buf_bytes::BufBytes;
+
+// 解放した領域に残っていたら困るデータ
+const SECRET: &[u8] = b"correct horse battery staple";
+const DATA: &[u8] = b"correct horse battery staple / correct horse battery staple / correct horse battery staple";
+
+// 解放するときに、領域にSECRETが残っていないか調べるアロケータ
+struct InspectingAlloc;
+
+thread_local! {
+    static LEAKED: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for InspectingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { System.alloc(layout) }
+    }
+
+    // reallocは上書きしないので、古い領域もここを通る
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let freed = unsafe { std::slice::from_raw_parts(ptr, layout.size()) };
+        if freed.windows(SECRET.len()).any(|w| w == SECRET) {
+            let _ = LEAKED.try_with(|n| n.set(n.get() + 1));
+        }
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: InspectingAlloc = InspectingAlloc;
+
+fn leaked_by(f: impl FnOnce()) -> usize {
+    let before = LEAKED.with(Cell::get);
+    f();
+    LEAKED.with(Cell::get) - before
+}
+
+// 消さずに解放したら見つかる
+#[test]
+fn detects_leak_test() {
+    assert_eq!(leaked_by(|| drop(SECRET.to_vec())), 1);
+}
+
+// Dropとinto_innerで、バッファを消してから解放する
+#[test]
+fn drop_wipes_buffer_test() {
+    let leaked = leaked_by(|| {
+        let mut bytes = BufBytes::with_capacity(DATA, 64).unwrap();
+        bytes.by_ref().take(3).for_each(drop);
+        drop(bytes);
+
+        let mut bytes = BufBytes::with_aligned_capacity(DATA, 64, 32).unwrap();
+        bytes.by_ref().take(3).for_each(drop);
+        drop(bytes);
+
+        let bytes = BufBytes::with_eager_error(DATA, 32).unwrap();
+        bytes.into_inner();
+    });
+    assert_eq!(leaked, 0);
+}
+
+// バッファを広げたり縮めたりしたとき、古い領域も消してから解放する
+#[test]
+fn resize_wipes_old_buffer_test() {
+    let leaked = leaked_by(|| {
+        let mut bytes = BufBytes::with_capacity(DATA, 32).unwrap();
+        assert_eq!(bytes.peek_n(64).unwrap().len(), 64);
+        bytes.shrink_buffer().unwrap();
+        drop(bytes);
+
+        let mut bytes = BufBytes::with_aligned_capacity(DATA, 32, 64).unwrap();
+        assert_eq!(bytes.peek_n(64).unwrap().len(), 64);
+        drop(bytes);
+    });
+    assert_eq!(leaked, 0);
+}
+
+// 消費したバイトの記録も、消してから解放する
+#[test]
+fn drop_wipes_consumed_bytes_test() {
+    let leaked = leaked_by(|| {
+        let mut bytes = BufBytes::with_recording(DATA, 16).unwrap();
+        bytes.by_ref().for_each(drop);
+        assert_eq!(bytes.recorded(), DATA);
+        drop(bytes);
+
+        let mut bytes = BufBytes::with_ring(DATA, 16, 40).unwrap();
+        bytes.by_ref().for_each(drop);
+        drop(bytes);
+
+        let mut bytes = BufBytes::with_rewind_cache(DATA, 16, 40).unwrap();
+        bytes.by_ref().for_each(drop);
+        bytes.rewind_by(30).unwrap();
+        drop(bytes);
+    });
+    assert_eq!(leaked, 0);
+}